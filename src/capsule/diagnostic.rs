@@ -0,0 +1,130 @@
+use std::{fmt::Display, ops::Range};
+
+use roxmltree::{Document, Node, TextPos};
+use strum::AsRefStr;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum CapsuleDiagnosticSeverity {
+    Warning,
+    Error,
+}
+
+/// A location inside of the capsule source, `line` and `column` are 1-based
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct CapsuleSpan {
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl CapsuleSpan {
+    #[must_use]
+    pub fn from_range(document: &Document, range: Range<usize>) -> Self {
+        let TextPos { row, col } = document.text_pos_at(range.start);
+
+        Self {
+            start: range.start,
+            end: range.end,
+            line: row,
+            column: col,
+        }
+    }
+
+    /// Converts a line/column position (as reported by roxmltree errors)
+    /// back into a byte offset into `text`
+    #[must_use]
+    pub fn from_text_pos(text: &str, pos: TextPos) -> Self {
+        let line_start: usize = text
+            .split_inclusive('\n')
+            .take(pos.row.saturating_sub(1) as usize)
+            .map(str::len)
+            .sum();
+        let start = text[line_start..]
+            .char_indices()
+            .nth(pos.col.saturating_sub(1) as usize)
+            .map_or(text.len(), |(i, _)| line_start + i);
+
+        Self {
+            start,
+            end: start,
+            line: pos.row,
+            column: pos.col,
+        }
+    }
+
+    #[must_use]
+    pub fn of_node(node: &Node) -> Self {
+        Self::from_range(node.document(), node.range())
+    }
+
+    /// Span of an attribute's value, or of the whole node if it has no such attribute
+    #[must_use]
+    pub fn of_attribute(node: &Node, name: &str) -> Self {
+        node.attribute_node(name).map_or_else(
+            || Self::of_node(node),
+            |attr| Self::from_range(node.document(), attr.range_value()),
+        )
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CapsuleDiagnostic {
+    pub severity: CapsuleDiagnosticSeverity,
+    pub message: String,
+    pub span: CapsuleSpan,
+}
+
+impl CapsuleDiagnostic {
+    #[must_use]
+    pub fn new<S>(severity: CapsuleDiagnosticSeverity, message: S, span: CapsuleSpan) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            severity,
+            message: message.into(),
+            span,
+        }
+    }
+
+    #[must_use]
+    pub fn warning<S>(message: S, span: CapsuleSpan) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(CapsuleDiagnosticSeverity::Warning, message, span)
+    }
+
+    #[must_use]
+    pub fn error<S>(message: S, span: CapsuleSpan) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(CapsuleDiagnosticSeverity::Error, message, span)
+    }
+
+    #[must_use]
+    pub const fn log_level(&self) -> log::Level {
+        match self.severity {
+            CapsuleDiagnosticSeverity::Warning => log::Level::Warn,
+            CapsuleDiagnosticSeverity::Error => log::Level::Error,
+        }
+    }
+}
+
+impl Display for CapsuleDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.span.line,
+            self.span.column,
+            self.severity.as_ref(),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for CapsuleDiagnostic {}
@@ -1,5 +1,8 @@
+pub mod diagnostic;
 pub mod obj;
 pub mod objs;
 pub mod parser;
+pub mod serializer;
+pub mod stylesheet;
 
 pub use obj::Capsule;
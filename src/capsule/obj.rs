@@ -1,14 +1,18 @@
 use std::{any::Any, fmt::Debug, sync::Arc};
 
-use macroquad::math::Rect;
+use macroquad::math::{Rect, Vec2};
 use orx_concurrent_vec::{ConcurrentElement, ConcurrentVec};
 use parking_lot::RwLock;
 
 use crate::{
-    capsule::objs::{script::CSScript, view::CSView},
+    capsule::{
+        objs::{font::CSFont, script::CSScript, view::CSView},
+        stylesheet::Stylesheet,
+    },
     event::CapsuleObjectEvent,
     layout::{computed::ComputedStyling, styling::Styling},
     lua::engine::LuaEngine,
+    renderer::backend::Renderer,
 };
 
 pub type ArcLock<T> = Arc<RwLock<T>>;
@@ -16,11 +20,12 @@ pub type BoxedCapsuleObject = Arc<dyn CapsuleObject + Sync + Send>;
 pub type CapsuleObjectChildren = Arc<ConcurrentVec<BoxedCapsuleObject>>;
 pub type CapsuleObjectEvents = Arc<ConcurrentVec<CapsuleObjectEvent>>;
 pub type CapsuleObjectId = ArcLock<Option<String>>;
+pub type CapsuleObjectClasses = ArcLock<Vec<String>>;
 
 pub trait CapsuleObject: Debug {
     fn as_any(&self) -> &dyn Any;
     fn base(&self) -> Arc<CapsuleObjectBase>;
-    fn render(&self);
+    fn render(&self, renderer: &mut dyn Renderer);
     fn bounding_box(&self) -> Rect {
         let base = self.base();
         let computed = base.computed_style.read();
@@ -28,6 +33,10 @@ pub trait CapsuleObject: Debug {
         Rect::new(computed.x, computed.y, computed.width, computed.height)
     }
 
+    fn hit_test(&self, point: Vec2) -> bool {
+        self.base().computed_style.read().hit_test(point)
+    }
+
     fn is_dirty(&self) -> bool {
         self.base().style.read().is_dirty()
     }
@@ -44,15 +53,19 @@ pub trait CapsuleObject: Debug {
 #[derive(Debug, Default, Clone)]
 pub struct CapsuleObjectBase {
     pub id: CapsuleObjectId,
+    pub classes: CapsuleObjectClasses,
     pub children: CapsuleObjectChildren,
     pub events: CapsuleObjectEvents,
     pub style: ArcLock<Styling>,
     pub computed_style: ArcLock<ComputedStyling>,
+    /// how far the children are scrolled, only used when `overflow` isn't `visible`
+    pub scroll: ArcLock<Vec2>,
 }
 
 #[derive(Debug, Default)]
 pub struct CapsuleObjectCreationContext {
     pub id: CapsuleObjectId,
+    pub classes: CapsuleObjectClasses,
     pub children: CapsuleObjectChildren,
     pub events: CapsuleObjectEvents,
     pub style: ArcLock<Styling>,
@@ -65,9 +78,11 @@ impl CapsuleObjectCreationContext {
         events: CapsuleObjectEvents,
         style: ArcLock<Styling>,
         id: CapsuleObjectId,
+        classes: CapsuleObjectClasses,
     ) -> Self {
         Self {
             id,
+            classes,
             children,
             events,
             style,
@@ -83,10 +98,29 @@ impl CapsuleObjectBase {
             style: ctx.style,
             events: ctx.events,
             id: ctx.id,
+            classes: ctx.classes,
             computed_style: Arc::default(),
+            scroll: Arc::default(),
         })
     }
 
+    /// Scrolls the children to `offset`, kept within what the last layout
+    /// allows
+    pub fn set_scroll(&self, offset: Vec2) {
+        let max_scroll = self.computed_style.read().max_scroll;
+        let offset = offset.clamp(Vec2::ZERO, max_scroll);
+
+        if *self.scroll.read() == offset {
+            return;
+        }
+
+        *self.scroll.write() = offset;
+
+        if let Some(hook) = self.style.read().layout_hook() {
+            hook.notify_scroll();
+        }
+    }
+
     #[must_use]
     pub fn children_vec(&self) -> Vec<BoxedCapsuleObject> {
         let mut out = Vec::new();
@@ -104,6 +138,8 @@ impl CapsuleObjectBase {
 pub struct CapsuleMeta {
     pub title: String,
     pub scripts: Vec<CSScript>,
+    pub stylesheet: Stylesheet,
+    pub fonts: Vec<CSFont>,
 }
 
 #[derive(Debug, Default)]
@@ -111,6 +147,8 @@ pub struct Capsule {
     pub meta: CapsuleMeta,
     pub view: CSView,
     pub lua: ArcLock<LuaEngine>,
+    /// size of the area the capsule is laid out in
+    pub viewport: Vec2,
 }
 
 impl Capsule {
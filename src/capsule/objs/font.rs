@@ -0,0 +1,32 @@
+use std::{path::Path, sync::Arc};
+
+use crate::layout::font::FontFace;
+
+/// A `<font>` of the meta, naming a font file so `font_family` can use it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CSFont {
+    pub name: String,
+    /// path of the font file, as it was written
+    pub src: String,
+    /// `None` until it's loaded, or when it couldn't be
+    pub face: Option<Arc<FontFace>>,
+}
+
+impl CSFont {
+    #[must_use]
+    pub const fn new(name: String, src: String) -> Self {
+        Self {
+            name,
+            src,
+            face: None,
+        }
+    }
+
+    /// Loads the font at `src`, relative to `dir`. Until it loads, text that
+    /// asks for it falls back to the next font of its `font_family`.
+    pub fn load(&mut self, dir: &Path) -> anyhow::Result<()> {
+        self.face = Some(Arc::new(FontFace::read(dir.join(&self.src))?));
+
+        Ok(())
+    }
+}
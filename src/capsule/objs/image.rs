@@ -0,0 +1,94 @@
+use std::{path::PathBuf, sync::Arc};
+
+use macroquad::math::Vec2;
+use parking_lot::RwLock;
+
+use crate::{
+    capsule::obj::{ArcLock, CapsuleObject, CapsuleObjectBase, CapsuleObjectCreationContext},
+    layout::font::FontSynthesis,
+    renderer::{backend::Renderer, bitmap::Bitmap},
+};
+
+#[derive(Debug, Default)]
+pub struct CSImage {
+    base: Arc<CapsuleObjectBase>,
+    /// path of the image, as it was written
+    pub src: ArcLock<String>,
+    /// drawn instead of the image when it couldn't be loaded
+    pub alt: ArcLock<String>,
+    /// what `src` is relative to, the directory of the capsule file
+    dir: PathBuf,
+    /// `None` when `src` couldn't be loaded
+    pub image: ArcLock<Option<Arc<Bitmap>>>,
+}
+
+impl CSImage {
+    /// An image without a source yet, see [`CSImage::set_src`]
+    #[must_use]
+    pub fn new(dir: PathBuf, alt: String, ctx: CapsuleObjectCreationContext) -> Self {
+        Self {
+            base: CapsuleObjectBase::new(ctx),
+            src: ArcLock::default(),
+            alt: RwLock::new(alt).into(),
+            dir,
+            image: ArcLock::default(),
+        }
+    }
+
+    /// Loads the PNG or JPEG at `src`. When it fails the error is returned
+    /// and the alt text is shown instead.
+    pub fn set_src(&self, src: String) -> anyhow::Result<()> {
+        let loaded = Bitmap::read(self.dir.join(&src)).map(Arc::new);
+
+        *self.src.write() = src;
+        *self.image.write() = loaded.as_ref().ok().cloned();
+        self.base.style.write().set_dirty();
+
+        loaded.map(drop)
+    }
+
+    /// Size of the image in pixels, `None` when it isn't loaded
+    #[must_use]
+    pub fn natural_size(&self) -> Option<Vec2> {
+        #[allow(clippy::cast_precision_loss)]
+        self.image
+            .read()
+            .as_ref()
+            .map(|image| Vec2::new(image.width as f32, image.height as f32))
+    }
+}
+
+impl CapsuleObject for CSImage {
+    fn base(&self) -> Arc<CapsuleObjectBase> {
+        self.base.clone()
+    }
+
+    fn render(&self, renderer: &mut dyn Renderer) {
+        let object_fit = self.base.style.read().object_fit;
+        let computed = self.base.computed_style.read();
+        let content = computed.content_box();
+
+        let Some(image) = self.image.read().clone() else {
+            renderer.draw_text(
+                &self.alt.read(),
+                content.point(),
+                computed.font.as_ref(),
+                FontSynthesis::default(),
+                computed.font_size.into(),
+                computed.color,
+            );
+            return;
+        };
+
+        #[allow(clippy::cast_precision_loss)]
+        let size = Vec2::new(image.width as f32, image.height as f32);
+
+        if let Some((source, destination)) = object_fit.place(size, content) {
+            renderer.draw_image(&image, source, destination);
+        }
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
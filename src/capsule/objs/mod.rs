@@ -1,3 +1,5 @@
+pub mod font;
+pub mod image;
 pub mod obj;
 pub mod script;
 pub mod text;
@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
-use crate::capsule::obj::{CapsuleObject, CapsuleObjectBase, CapsuleObjectCreationContext};
+use crate::{
+    capsule::obj::{CapsuleObject, CapsuleObjectBase, CapsuleObjectCreationContext},
+    renderer::backend::Renderer,
+};
 
 // like a div
 #[derive(Debug, Default)]
@@ -22,7 +25,7 @@ impl CapsuleObject for CSObj {
         self.base.clone()
     }
 
-    fn render(&self) {}
+    fn render(&self, _renderer: &mut dyn Renderer) {}
 
     fn as_any(&self) -> &dyn std::any::Any {
         self
@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
-use crate::capsule::obj::{CapsuleObject, CapsuleObjectBase};
+use crate::{
+    capsule::obj::{CapsuleObject, CapsuleObjectBase},
+    renderer::backend::Renderer,
+};
 
 #[derive(Debug, Default, Clone)]
 pub struct CSScript {
@@ -23,7 +26,7 @@ impl CapsuleObject for CSScript {
         self.base.clone()
     }
 
-    fn render(&self) {}
+    fn render(&self, _renderer: &mut dyn Renderer) {}
 
     fn as_any(&self) -> &dyn std::any::Any {
         self
@@ -1,30 +1,113 @@
 use std::sync::Arc;
 
+use macroquad::math::{Rect, Vec2};
 use parking_lot::RwLock;
 
 use crate::{
-    capsule::obj::{ArcLock, CapsuleObject, CapsuleObjectBase, CapsuleObjectCreationContext},
-    layout::capsule::color::WHITE,
-    renderer::text::draw_text_top_left,
+    capsule::{
+        obj::{ArcLock, CapsuleObject, CapsuleObjectBase, CapsuleObjectCreationContext},
+        objs::font::CSFont,
+    },
+    layout::{
+        capsule::{color::COColor, font_family::COFontFamily},
+        computed::ComputedStyling,
+        font::FontSynthesis,
+        text::{TextLine, TextStyle, decoration_thickness},
+    },
+    renderer::backend::Renderer,
 };
 
+/// How a run of text differs from the text it's in, set by the `<b>`,
+/// `<i>`, `<u>`, `<s>` and `<span>` elements around it
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RunStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// `None` to take the text's
+    pub color: Option<COColor>,
+    pub font_size: Option<u16>,
+    /// empty to take the text's
+    pub font_family: COFontFamily,
+}
+
+impl RunStyle {
+    /// Resolves the style against the computed style of the text, picking
+    /// its font out of `fonts`
+    #[must_use]
+    pub fn resolve(&self, text: &ComputedStyling, fonts: &[CSFont]) -> TextStyle {
+        TextStyle {
+            font: if self.font_family.0.is_empty() {
+                text.font.clone()
+            } else {
+                self.font_family.resolve(fonts)
+            },
+            font_size: self.font_size.unwrap_or(text.font_size).into(),
+            color: self.color.unwrap_or(text.color),
+            synthesis: FontSynthesis {
+                bold: self.bold,
+                italic: self.italic,
+            },
+            underline: self.underline,
+            strikethrough: self.strikethrough,
+        }
+    }
+}
+
+/// A part of a text that's styled alike
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TextRun {
+    pub text: String,
+    pub style: RunStyle,
+}
+
+impl TextRun {
+    #[must_use]
+    pub fn plain(text: String) -> Self {
+        Self {
+            text,
+            style: RunStyle::default(),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct CSText {
     base: Arc<CapsuleObjectBase>,
-    pub text: ArcLock<String>,
+    /// the text, in runs that are styled alike
+    pub runs: ArcLock<Vec<TextRun>>,
+    /// `runs` wrapped to the width of the content box, filled in by layout
+    pub lines: ArcLock<Vec<TextLine>>,
 }
 
 impl CSText {
     #[must_use]
-    pub fn new(text: String, ctx: CapsuleObjectCreationContext) -> Self {
+    pub fn new(runs: Vec<TextRun>, ctx: CapsuleObjectCreationContext) -> Self {
         Self {
-            text: RwLock::new(text).into(),
+            runs: RwLock::new(runs).into(),
+            lines: ArcLock::default(),
             base: CapsuleObjectBase::new(ctx),
         }
     }
 
+    /// The text of all the runs, without their styles
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.runs
+            .read()
+            .iter()
+            .map(|run| run.text.as_str())
+            .collect()
+    }
+
+    /// Replaces the text with unstyled `text`
     pub fn set_text(&self, text: String) {
-        *self.text.write() = text;
+        self.set_runs(vec![TextRun::plain(text)]);
+    }
+
+    pub fn set_runs(&self, runs: Vec<TextRun>) {
+        *self.runs.write() = runs;
         self.base.style.write().set_dirty();
     }
 }
@@ -34,17 +117,52 @@ impl CapsuleObject for CSText {
         self.base.clone()
     }
 
-    fn render(&self) {
+    fn render(&self, renderer: &mut dyn Renderer) {
         let computed = self.base.computed_style.read();
-        let style = self.base.style.read();
-
-        draw_text_top_left(
-            &self.text.read(),
-            computed.x,
-            computed.y,
-            style.font_size.into(),
-            style.color.unwrap_or(WHITE).as_macroquad(),
-        );
+        let content = computed.content_box();
+
+        let mut y = content.y;
+
+        for line in self.lines.read().iter() {
+            for fragment in &line.fragments {
+                let style = &fragment.style;
+                let x = content.x + fragment.x;
+
+                renderer.draw_text(
+                    &fragment.text,
+                    Vec2::new(x, y + fragment.y),
+                    style.font.as_ref(),
+                    style.synthesis,
+                    style.font_size,
+                    style.color,
+                );
+
+                let thickness = decoration_thickness(style.font_size);
+                let baseline = y + line.baseline;
+
+                if style.underline {
+                    renderer.fill_rect(
+                        Rect::new(x, baseline + thickness, fragment.width, thickness),
+                        style.color,
+                    );
+                }
+
+                // through the middle of lowercase letters
+                if style.strikethrough {
+                    renderer.fill_rect(
+                        Rect::new(
+                            x,
+                            baseline - (style.font_size * 0.25).round() - thickness / 2.0,
+                            fragment.width,
+                            thickness,
+                        ),
+                        style.color,
+                    );
+                }
+            }
+
+            y += line.height;
+        }
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
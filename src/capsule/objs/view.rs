@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::{
     capsule::obj::{CapsuleObject, CapsuleObjectBase, CapsuleObjectCreationContext},
     impl_obj_traits,
+    renderer::backend::Renderer,
 };
 
 #[derive(Debug, Default, Clone)]
@@ -24,7 +25,7 @@ impl CapsuleObject for CSView {
         self.base.clone()
     }
 
-    fn render(&self) {}
+    fn render(&self, _renderer: &mut dyn Renderer) {}
 
     fn as_any(&self) -> &dyn std::any::Any {
         self
@@ -1,5 +1,6 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
+use anyhow::Context;
 use orx_concurrent_vec::ConcurrentVec;
 use parking_lot::RwLock;
 use roxmltree::Node;
@@ -7,75 +8,117 @@ use roxmltree::Node;
 use crate::{
     capsule::{
         Capsule,
+        diagnostic::{CapsuleDiagnostic, CapsuleSpan},
         obj::{BoxedCapsuleObject, CapsuleMeta, CapsuleObjectCreationContext},
-        objs::{obj::CSObj, script::CSScript, text::CSText, view::CSView},
+        objs::{
+            font::CSFont,
+            image::CSImage,
+            obj::CSObj,
+            script::CSScript,
+            text::{CSText, RunStyle, TextRun},
+            view::CSView,
+        },
+        stylesheet::{SelectorSubject, Stylesheet},
     },
     event::CapsuleObjectEvent,
     layout::{
         capsule::{
-            align::COAlignItems, color::COColor, dimension::CODimension, flexdir::COFlexDirection,
+            align::COAlignItems,
+            align_content::COAlignContent,
+            align_self::COAlignSelf,
+            border_style::COBorderStyle,
+            color::{BLACK, COColor},
+            corners::COCorners,
+            dimension::{COCalc, CODimension},
+            display::CODisplay,
+            edges::COEdges,
+            flexdir::COFlexDirection,
+            font_family::COFontFamily,
+            grid::{COGridPlacement, COGridTrack, COGridTracks},
             justify::COJustifyContent,
+            object_fit::COObjectFit,
+            overflow::COOverflow,
+            position::COPosition,
+            shadow::{COBoxShadow, COShadow},
+            transform::{COTransform, COTransformFunction, COTransformOrigin},
+            visibility::COVisibility,
+            wrap::COFlexWrap,
         },
         styling::Styling,
     },
     renderer::constants::BR_LINE_HEIGHT,
 };
 
-macro_rules! log_bad_property {
-    ($name:expr) => {
-        let _tt = stringify!($name);
-        log::warn!("bad {_tt} property: '{}'", $name);
+macro_rules! enum_prop {
+    ($style: ident, $name: ident, $value: ident, $type: ty) => {
+        $style.$name = $value
+            .parse::<$type>()
+            .map_err(|_| StylePropertyError::BadValue)?
     };
 }
 
-macro_rules! enum_attr {
-    ($child: ident, $style: ident, $name: ident, $type: ty) => {
-        if let Some(value) = $child.attribute(stringify!($name)) {
-            if let Ok(parsed) = value.parse::<$type>() {
-                $style.$name = parsed;
-            } else {
-                log_bad_property!(value);
-            }
-        }
+macro_rules! dimension_prop {
+    ($style: ident, $name: ident, $value: ident) => {
+        $style.$name = Some(try_parse_dimension($value).ok_or(StylePropertyError::BadValue)?)
     };
 }
 
-macro_rules! dimension_attr {
-    ($child: ident, $style: ident, $name: ident) => {
-        if let Some(value) = $child.attribute(stringify!($name)) {
-            if let Some(parsed) = try_parse_dimension(value) {
-                $style.$name = Some(parsed);
-            } else {
-                log_bad_property!(value);
-            }
-        }
+macro_rules! edges_prop {
+    ($style: ident, $name: ident, $value: ident) => {
+        $style.$name = try_parse_edges($value).ok_or(StylePropertyError::BadValue)?
+    };
+    ($style: ident, $name: ident, $side: ident, $value: ident) => {
+        $style.$name.$side = try_parse_dimension($value).ok_or(StylePropertyError::BadValue)?
     };
 }
 
-macro_rules! color_attr {
-    ($child: ident, $style: ident, $name: ident) => {
-        if let Some(value) = $child.attribute(stringify!($name)) {
-            if let Some(parsed) = try_parse_color(value) {
-                $style.$name = Some(parsed);
-            } else {
-                log_bad_property!(value);
-            }
+macro_rules! corners_prop {
+    ($style: ident, $name: ident, $value: ident) => {
+        $style.$name = try_parse_corners($value).ok_or(StylePropertyError::BadValue)?
+    };
+    ($style: ident, $name: ident, $corner: ident, $value: ident) => {
+        $style.$name.$corner = try_parse_dimension($value).ok_or(StylePropertyError::BadValue)?
+    };
+}
+
+/// `none` unsets the color, e.g. to take back one set by a stylesheet
+macro_rules! color_prop {
+    ($style: ident, $name: ident, $value: ident) => {
+        $style.$name = match $value.trim() {
+            "none" => None,
+            value => Some(try_parse_color(value).ok_or(StylePropertyError::BadValue)?),
         }
     };
 }
 
-macro_rules! primitive_attr {
-    ($child: ident, $style: ident, $name: ident, $type: tt) => {
-        if let Some(value) = $child.attribute(stringify!($name)) {
-            if let Ok(parsed) = value.parse::<$type>() {
-                $style.$name = parsed;
-            } else {
-                log_bad_property!(value);
-            }
+/// `auto` unsets the property, like it does `z_index`
+macro_rules! optional_prop {
+    ($style: ident, $name: ident, $value: ident, $type: tt) => {
+        optional_prop!($style, $name, $value, $type, Some)
+    };
+    ($style: ident, $name: ident, $value: ident, $type: tt, $valid: expr) => {
+        $style.$name = match $value.trim() {
+            "auto" => None,
+            value => Some(primitive_prop!(value, $type, $valid)),
         }
     };
 }
 
+/// `$valid` turns the values that parse but can't be used into `None`
+macro_rules! primitive_prop {
+    ($style: ident, $name: ident, $value: ident, $type: tt, $valid: expr) => {
+        $style.$name = primitive_prop!($value, $type, $valid)
+    };
+    ($value: expr, $type: tt, $valid: expr) => {
+        $value
+            .trim()
+            .parse::<$type>()
+            .ok()
+            .and_then($valid)
+            .ok_or(StylePropertyError::BadValue)?
+    };
+}
+
 macro_rules! event_attr {
     ($child: ident, $events: ident, $name: ident) => {
         if let Some(value) = $child.attribute(stringify!($name)) {
@@ -84,6 +127,141 @@ macro_rules! event_attr {
     };
 }
 
+/// Attributes that are not style properties
+const NON_STYLE_ATTRIBUTES: &[&str] = &["id", "class", "onclick", "onresize"];
+
+/// Attributes of `<image>` that aren't style properties either
+const IMAGE_ATTRIBUTES: &[&str] = &["src", "alt"];
+
+/// Style properties the elements inside `<text>` can set
+const INLINE_PROPERTIES: &[&str] = &["color", "font_size", "font_family"];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StylePropertyError {
+    Unknown,
+    BadValue,
+}
+
+/// Sets the style property `name` from its textual `value`, this is shared by
+/// element attributes and stylesheet declarations
+pub fn apply_style_property(
+    style: &mut Styling,
+    name: &str,
+    value: &str,
+) -> Result<(), StylePropertyError> {
+    match name {
+        "font_size" => optional_prop!(style, font_size, value, u16),
+        "font_family" => {
+            style.font_family = try_parse_font_family(value).ok_or(StylePropertyError::BadValue)?;
+        }
+        "width" => dimension_prop!(style, width, value),
+        "height" => dimension_prop!(style, height, value),
+        "min_width" => dimension_prop!(style, min_width, value),
+        "max_width" => dimension_prop!(style, max_width, value),
+        "min_height" => dimension_prop!(style, min_height, value),
+        "max_height" => dimension_prop!(style, max_height, value),
+        "aspect_ratio" => {
+            style.aspect_ratio = match value.trim() {
+                "auto" => None,
+                _ => Some(try_parse_ratio(value).ok_or(StylePropertyError::BadValue)?),
+            };
+        }
+        "object_fit" => enum_prop!(style, object_fit, value, COObjectFit),
+        "align" => enum_prop!(style, align, value, COAlignItems),
+        "justify" => enum_prop!(style, justify, value, COJustifyContent),
+        "flexdir" => enum_prop!(style, flexdir, value, COFlexDirection),
+        "flex_wrap" => enum_prop!(style, flex_wrap, value, COFlexWrap),
+        "align_self" => enum_prop!(style, align_self, value, COAlignSelf),
+        "align_content" => enum_prop!(style, align_content, value, COAlignContent),
+        "flex_grow" => primitive_prop!(style, flex_grow, value, f32, valid_flex_factor),
+        "flex_shrink" => optional_prop!(style, flex_shrink, value, f32, valid_flex_factor),
+        "flex_basis" => dimension_prop!(style, flex_basis, value),
+        "color" => color_prop!(style, color, value),
+        "background_color" => color_prop!(style, background_color, value),
+        "margin" => edges_prop!(style, margin, value),
+        "margin_top" => edges_prop!(style, margin, top, value),
+        "margin_right" => edges_prop!(style, margin, right, value),
+        "margin_bottom" => edges_prop!(style, margin, bottom, value),
+        "margin_left" => edges_prop!(style, margin, left, value),
+        "padding" => edges_prop!(style, padding, value),
+        "padding_top" => edges_prop!(style, padding, top, value),
+        "padding_right" => edges_prop!(style, padding, right, value),
+        "padding_bottom" => edges_prop!(style, padding, bottom, value),
+        "padding_left" => edges_prop!(style, padding, left, value),
+        "border_width" => edges_prop!(style, border_width, value),
+        "border_width_top" => edges_prop!(style, border_width, top, value),
+        "border_width_right" => edges_prop!(style, border_width, right, value),
+        "border_width_bottom" => edges_prop!(style, border_width, bottom, value),
+        "border_width_left" => edges_prop!(style, border_width, left, value),
+        "border_color" => color_prop!(style, border_color, value),
+        "border_style" => enum_prop!(style, border_style, value, COBorderStyle),
+        "border_radius" => corners_prop!(style, border_radius, value),
+        "border_radius_top_left" => corners_prop!(style, border_radius, top_left, value),
+        "border_radius_top_right" => corners_prop!(style, border_radius, top_right, value),
+        "border_radius_bottom_right" => corners_prop!(style, border_radius, bottom_right, value),
+        "border_radius_bottom_left" => corners_prop!(style, border_radius, bottom_left, value),
+        "box_shadow" => {
+            style.box_shadow = try_parse_box_shadow(value).ok_or(StylePropertyError::BadValue)?;
+        }
+        "position" => enum_prop!(style, position, value, COPosition),
+        "overflow" => enum_prop!(style, overflow, value, COOverflow),
+        "display" => enum_prop!(style, display, value, CODisplay),
+        "visibility" => enum_prop!(style, visibility, value, COVisibility),
+        "grid_template_columns" => {
+            style.grid_template_columns =
+                try_parse_grid_tracks(value).ok_or(StylePropertyError::BadValue)?;
+        }
+        "grid_template_rows" => {
+            style.grid_template_rows =
+                try_parse_grid_tracks(value).ok_or(StylePropertyError::BadValue)?;
+        }
+        "grid_column" => {
+            style.grid_column =
+                try_parse_grid_placement(value).ok_or(StylePropertyError::BadValue)?;
+        }
+        "grid_row" => {
+            style.grid_row = try_parse_grid_placement(value).ok_or(StylePropertyError::BadValue)?;
+        }
+        "transform" => {
+            style.transform = try_parse_transform(value).ok_or(StylePropertyError::BadValue)?;
+        }
+        "transform_origin" => {
+            style.transform_origin =
+                try_parse_transform_origin(value).ok_or(StylePropertyError::BadValue)?;
+        }
+        "row_gap" => dimension_prop!(style, row_gap, value),
+        "column_gap" => dimension_prop!(style, column_gap, value),
+        "gap" => {
+            // `gap="row column"`, or one value for both
+            let values = split_values(value)
+                .ok_or(StylePropertyError::BadValue)?
+                .into_iter()
+                .map(try_parse_dimension)
+                .collect::<Option<Vec<_>>>()
+                .ok_or(StylePropertyError::BadValue)?;
+
+            match values[..] {
+                [gap] => (style.row_gap, style.column_gap) = (Some(gap), Some(gap)),
+                [row, column] => (style.row_gap, style.column_gap) = (Some(row), Some(column)),
+                _ => return Err(StylePropertyError::BadValue),
+            }
+        }
+        "top" => dimension_prop!(style, top, value),
+        "right" => dimension_prop!(style, right, value),
+        "bottom" => dimension_prop!(style, bottom, value),
+        "left" => dimension_prop!(style, left, value),
+        "z_index" => {
+            style.z_index = match value {
+                "auto" => None,
+                _ => Some(value.parse().map_err(|_| StylePropertyError::BadValue)?),
+            };
+        }
+        _ => return Err(StylePropertyError::Unknown),
+    }
+
+    Ok(())
+}
+
 #[must_use]
 pub fn try_parse_color(color: &str) -> Option<COColor> {
     if let Some([r, g, b, a]) = parse_color::parse(color) {
@@ -109,29 +287,574 @@ pub fn try_parse_color(color: &str) -> Option<COColor> {
     Some(COColor::from_rgba(r, g, b, a))
 }
 
+/// Parses a single number with an optional unit, e.g. `12`, `12px`, `50%` or `1.5em`
+fn try_parse_unit(text: &str) -> Option<CODimension> {
+    let number = |suffix: &str| text[..text.len() - suffix.len()].parse::<f32>().ok();
+
+    if text.ends_with("px") {
+        number("px").map(CODimension::Points)
+    } else if text.ends_with('%') {
+        number("%").map(|v| CODimension::Percent(v / 100.0))
+    } else if text.ends_with("rem") {
+        number("rem").map(CODimension::Rem)
+    } else if text.ends_with("em") {
+        number("em").map(CODimension::Em)
+    } else if text.ends_with("vw") {
+        number("vw").map(CODimension::Vw)
+    } else if text.ends_with("vh") {
+        number("vh").map(CODimension::Vh)
+    } else {
+        number("").map(CODimension::Points)
+    }
+}
+
+/// Parses the inside of `calc(...)`: terms joined by `+` and `-`
+fn try_parse_calc(text: &str) -> Option<COCalc> {
+    let mut calc = COCalc::default();
+    let mut rest = text.trim();
+    let mut sign = 1.0;
+
+    loop {
+        // a term ends at the first operator that isn't its own sign
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(i, c)| (c == '+' || c == '-') && !rest[..i].ends_with(['e', 'E']))
+            .map_or(rest.len(), |(i, _)| i);
+
+        calc.add(try_parse_unit(rest[..end].trim())?, sign)?;
+
+        rest = rest[end..].trim_start();
+        sign = match rest.chars().next() {
+            None => return Some(calc),
+            Some('+') => 1.0,
+            Some('-') => -1.0,
+            Some(_) => return None,
+        };
+        rest = rest[1..].trim_start();
+    }
+}
+
 #[must_use]
 pub fn try_parse_dimension(text: &str) -> Option<CODimension> {
+    let text = text.trim();
+
     if text == "auto" {
         return Some(CODimension::Auto);
     } else if text == "undefined" {
         return Some(CODimension::Undefined);
-    } else if text.ends_with('%') {
-        return Some(CODimension::Percent(
-            text.strip_suffix("%").unwrap().parse::<f32>().ok()? / 100.0,
-        ));
+    } else if let Some(expr) = text.strip_prefix("calc(").and_then(|t| t.strip_suffix(')')) {
+        return Some(CODimension::Calc(try_parse_calc(expr)?));
     }
 
-    Some(CODimension::Points(text.parse::<f32>().ok()?))
+    try_parse_unit(text)
+}
+
+/// Parses a positive ratio, either as a number (`1.5`) or a fraction (`16/9`)
+#[must_use]
+pub fn try_parse_ratio(text: &str) -> Option<f32> {
+    let ratio = match text.split_once('/') {
+        Some((width, height)) => {
+            width.trim().parse::<f32>().ok()? / height.trim().parse::<f32>().ok()?
+        }
+        None => text.trim().parse::<f32>().ok()?,
+    };
+
+    valid_ratio(ratio)
+}
+
+/// `ratio` if it can be an aspect ratio, finite and above zero
+#[must_use]
+pub fn valid_ratio(ratio: f32) -> Option<f32> {
+    (ratio.is_finite() && ratio > 0.0).then_some(ratio)
+}
+
+/// `factor` if it can be a `flex_grow` or `flex_shrink`, finite and not
+/// negative
+#[must_use]
+pub fn valid_flex_factor(factor: f32) -> Option<f32> {
+    (factor.is_finite() && factor >= 0.0).then_some(factor)
+}
+
+/// Splits a list of values separated by whitespace outside of parentheses,
+/// so `calc()` expressions stay in one piece
+fn split_values(text: &str) -> Option<Vec<&str>> {
+    let mut values = Vec::new();
+    let mut depth = 0usize;
+    let mut start = None;
+
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1)?,
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(start) = start.take() {
+                    values.push(&text[start..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        start.get_or_insert(i);
+    }
+
+    (depth == 0).then_some(values)
+}
+
+/// Parses a 1 to 4 value shorthand like `padding="4 8"`
+#[must_use]
+pub fn try_parse_edges(text: &str) -> Option<COEdges> {
+    let values = split_values(text)?
+        .into_iter()
+        .map(try_parse_dimension)
+        .collect::<Option<Vec<_>>>()?;
+
+    COEdges::from_shorthand(&values)
+}
+
+/// Parses a 1 to 4 value shorthand like `border_radius="8 0"`
+#[must_use]
+pub fn try_parse_corners(text: &str) -> Option<COCorners> {
+    let values = split_values(text)?
+        .into_iter()
+        .map(try_parse_dimension)
+        .collect::<Option<Vec<_>>>()?;
+
+    COCorners::from_shorthand(&values)
+}
+
+/// Parses a list of shadows separated by commas, each an x and y offset,
+/// an optional blur and spread, and an optional color before or after them,
+/// like `box_shadow="0 2 8 #00000080"`. `none` is an empty list.
+#[must_use]
+pub fn try_parse_box_shadow(text: &str) -> Option<COBoxShadow> {
+    if text.trim() == "none" {
+        return Some(COBoxShadow::default());
+    }
+
+    let mut shadows = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices().chain([(text.len(), ',')]) {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1)?,
+            ',' if depth == 0 => {
+                shadows.push(try_parse_shadow(&text[start..i])?);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    Some(COBoxShadow(shadows))
+}
+
+fn try_parse_shadow(text: &str) -> Option<COShadow> {
+    let mut values = split_values(text)?;
+    let mut color = None;
+
+    for i in [values.len().checked_sub(1)?, 0] {
+        if try_parse_dimension(values[i]).is_none() {
+            color = Some(try_parse_color(values.remove(i))?);
+            break;
+        }
+    }
+
+    let values = values
+        .into_iter()
+        .map(try_parse_dimension)
+        .collect::<Option<Vec<_>>>()?;
+    let zero = CODimension::Points(0.0);
+    let (offset_x, offset_y, blur, spread) = match values[..] {
+        [x, y] => (x, y, zero, zero),
+        [x, y, blur] => (x, y, blur, zero),
+        [x, y, blur, spread] => (x, y, blur, spread),
+        _ => return None,
+    };
+
+    Some(COShadow {
+        offset_x,
+        offset_y,
+        blur,
+        spread,
+        color: color.unwrap_or(BLACK),
+    })
+}
+
+/// Parses font names separated by commas, most wanted first, like
+/// `font_family="'Brand Sans', default"`. Names can be quoted but can't
+/// contain commas. `inherit` is an empty list.
+#[must_use]
+pub fn try_parse_font_family(text: &str) -> Option<COFontFamily> {
+    if text.trim() == "inherit" {
+        return Some(COFontFamily::default());
+    }
+
+    text.split(',')
+        .map(|name| {
+            let name = name.trim();
+            let unquoted = ['\'', '"']
+                .into_iter()
+                .find_map(|quote| name.strip_prefix(quote)?.strip_suffix(quote))
+                .unwrap_or(name);
+
+            (!unquoted.is_empty() && !unquoted.contains(['\'', '"'])).then(|| unquoted.to_owned())
+        })
+        .collect::<Option<_>>()
+        .map(COFontFamily)
+}
+
+/// Parses a track list like `grid_template_columns="200 1fr auto"`
+#[must_use]
+pub fn try_parse_grid_tracks(text: &str) -> Option<COGridTracks> {
+    if text.trim() == "none" {
+        return Some(COGridTracks::default());
+    }
+
+    let tracks = split_values(text)?
+        .into_iter()
+        .map(|value| match value {
+            "auto" => Some(COGridTrack::Auto),
+            value if value.ends_with("fr") => value
+                .trim_end_matches("fr")
+                .parse::<f32>()
+                .ok()
+                .filter(|v| *v >= 0.0)
+                .map(COGridTrack::Fraction),
+            value => match try_parse_dimension(value)? {
+                CODimension::Auto | CODimension::Undefined => None,
+                dimension => Some(COGridTrack::Fixed(dimension)),
+            },
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    (!tracks.is_empty()).then_some(COGridTracks(tracks))
+}
+
+/// Parses `auto`, `2`, `span 2`, `1 / 3` or `1 / span 2`. Lines are 1-based.
+#[must_use]
+pub fn try_parse_grid_placement(text: &str) -> Option<COGridPlacement> {
+    fn span(text: &str) -> Option<u16> {
+        text.trim()
+            .strip_prefix("span")?
+            .trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|span| *span > 0)
+    }
+
+    fn line(text: &str) -> Option<i16> {
+        text.trim().parse::<i16>().ok().filter(|line| *line != 0)
+    }
+
+    let (start, end) = match text.split_once('/') {
+        Some((start, end)) => (start, Some(end)),
+        None => (text, None),
+    };
+
+    if start.trim() == "auto" && end.is_none() {
+        return Some(COGridPlacement::default());
+    }
+
+    if let Some(span) = span(start) {
+        return end.is_none().then_some(COGridPlacement {
+            start: None,
+            end: None,
+            span,
+        });
+    }
+
+    let start = line(start)?;
+    let (end, span) = match end {
+        None => (None, 1),
+        Some(end) => match (span(end), line(end)) {
+            (Some(span), _) => (None, span),
+            // lines counted from the start can be turned into a span already
+            (None, Some(end)) if start > 0 && end > 0 => {
+                (None, u16::try_from(end - start).ok().filter(|s| *s > 0)?)
+            }
+            (None, Some(end)) => (Some(end), 1),
+            (None, None) => return None,
+        },
+    };
+
+    Some(COGridPlacement {
+        start: Some(start),
+        end,
+        span,
+    })
+}
+
+/// Parses an angle in `deg`, `rad` or `turn`, plain numbers are degrees
+fn try_parse_angle(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let (value, unit) = [
+        ("deg", 1.0),
+        ("rad", 180.0 / std::f32::consts::PI),
+        ("turn", 360.0),
+    ]
+    .into_iter()
+    .find_map(|(suffix, unit)| Some((text.strip_suffix(suffix)?, unit)))
+    .unwrap_or((text, 1.0));
+
+    value.trim().parse::<f32>().ok().map(|v| v * unit)
+}
+
+/// Parses a list of transform functions like
+/// `transform="translate(10, 50%) rotate(45deg) scale(2)"`, or `none`
+#[must_use]
+pub fn try_parse_transform(text: &str) -> Option<COTransform> {
+    if text.trim() == "none" {
+        return Some(COTransform::default());
+    }
+
+    let functions = split_values(text)?
+        .into_iter()
+        .map(|function| {
+            let (name, args) = function.strip_suffix(')')?.split_once('(')?;
+            let args: Vec<_> = args.split(',').map(str::trim).collect();
+
+            match (name.trim(), &args[..]) {
+                ("translate", [x]) => Some(COTransformFunction::Translate(
+                    try_parse_dimension(x)?,
+                    CODimension::Points(0.0),
+                )),
+                ("translate", [x, y]) => Some(COTransformFunction::Translate(
+                    try_parse_dimension(x)?,
+                    try_parse_dimension(y)?,
+                )),
+                ("rotate", [angle]) => Some(COTransformFunction::Rotate(try_parse_angle(angle)?)),
+                ("scale", [both]) => {
+                    let both = both.parse().ok()?;
+                    Some(COTransformFunction::Scale(both, both))
+                }
+                ("scale", [x, y]) => {
+                    Some(COTransformFunction::Scale(x.parse().ok()?, y.parse().ok()?))
+                }
+                _ => None,
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    (!functions.is_empty()).then_some(COTransform(functions))
+}
+
+/// Parses one or two values, keywords like `left` or `bottom` are turned
+/// into percentages. A single value is used for x and y is centered.
+#[must_use]
+pub fn try_parse_transform_origin(text: &str) -> Option<COTransformOrigin> {
+    fn value(text: &str) -> Option<CODimension> {
+        match text {
+            "left" | "top" => Some(CODimension::Percent(0.0)),
+            "center" => Some(CODimension::Percent(0.5)),
+            "right" | "bottom" => Some(CODimension::Percent(1.0)),
+            _ => try_parse_dimension(text),
+        }
+    }
+
+    match split_values(text)?[..] {
+        [x] => Some(COTransformOrigin {
+            x: value(x)?,
+            ..COTransformOrigin::default()
+        }),
+        [x, y] => Some(COTransformOrigin {
+            x: value(x)?,
+            y: value(y)?,
+        }),
+        _ => None,
+    }
 }
 
 #[must_use]
 #[allow(clippy::needless_pass_by_value)]
 pub fn clean_text(s: String) -> String {
-    s.lines().map(str::trim).collect::<Vec<_>>().join("\n")
+    s.lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_owned()
+}
+
+/// Returns the cleaned up text of `node`, or reports a diagnostic and returns
+/// an empty string if it has none
+fn required_text(node: Node, diagnostics: &mut Vec<CapsuleDiagnostic>) -> String {
+    node.text()
+        .map(std::string::ToString::to_string)
+        .map(clean_text)
+        .unwrap_or_else(|| {
+            diagnostics.push(CapsuleDiagnostic::warning(
+                format!("'{}' has no text", node.tag_name().name()),
+                CapsuleSpan::of_node(&node),
+            ));
+            String::new()
+        })
+}
+
+/// Cleans up the text of `runs` like [`clean_text`], as if they were one
+/// string, and merges the runs that end up next to one with the same style
+fn clean_runs(runs: &[TextRun]) -> Vec<TextRun> {
+    let plain = RunStyle::default();
+    // whitespace at the edges of a run isn't underlined or struck through,
+    // decorations stop at the words they're around
+    let edges: Vec<_> = runs
+        .iter()
+        .map(|run| RunStyle {
+            underline: false,
+            strikethrough: false,
+            ..run.style.clone()
+        })
+        .collect();
+    let chars: Vec<_> = runs
+        .iter()
+        .zip(&edges)
+        .flat_map(|(run, edge)| {
+            let start = run.text.len() - run.text.trim_start().len();
+            let end = start + run.text.trim().len();
+
+            run.text.char_indices().map(move |(i, c)| {
+                let style = if (start..end).contains(&i) {
+                    &run.style
+                } else {
+                    edge
+                };
+                (c, style)
+            })
+        })
+        .collect();
+    let mut cleaned = Vec::new();
+
+    for line in chars.split(|(c, _)| *c == '\n') {
+        let start = line
+            .iter()
+            .position(|(c, _)| !c.is_whitespace())
+            .unwrap_or(line.len());
+        let end = line
+            .iter()
+            .rposition(|(c, _)| !c.is_whitespace())
+            .map_or(start, |end| end + 1);
+
+        // line breaks belong to the text, not to the run they're in
+        if !cleaned.is_empty() {
+            cleaned.push(('\n', &plain));
+        }
+        cleaned.extend_from_slice(&line[start..end]);
+    }
+
+    while cleaned.last().is_some_and(|(c, _)| *c == '\n') {
+        cleaned.pop();
+    }
+
+    let mut cleaned_runs: Vec<TextRun> = Vec::new();
+
+    for (c, style) in cleaned {
+        match cleaned_runs.last_mut() {
+            Some(run) if run.style == *style => run.text.push(c),
+            _ => cleaned_runs.push(TextRun {
+                text: c.to_string(),
+                style: style.clone(),
+            }),
+        }
+    }
+
+    cleaned_runs
+}
+
+/// Collects the text inside `node` into `runs`, styled by `style` and the
+/// `<b>`, `<i>`, `<u>`, `<s>` and `<span>` elements it's nested in
+fn parse_runs(
+    node: Node,
+    style: &RunStyle,
+    runs: &mut Vec<TextRun>,
+    diagnostics: &mut Vec<CapsuleDiagnostic>,
+) {
+    for child in node.children() {
+        if child.is_text() {
+            runs.push(TextRun {
+                text: child.text().unwrap_or_default().to_owned(),
+                style: style.clone(),
+            });
+            continue;
+        }
+
+        if !child.is_element() {
+            continue;
+        }
+
+        let tag_name = child.tag_name().name();
+        let mut style = style.clone();
+
+        match tag_name {
+            "b" => style.bold = true,
+            "i" => style.italic = true,
+            "u" => style.underline = true,
+            "s" => style.strikethrough = true,
+            "span" => {}
+            _ => {
+                diagnostics.push(CapsuleDiagnostic::warning(
+                    format!("unknown node type: '{tag_name}'"),
+                    CapsuleSpan::of_node(&child),
+                ));
+                continue;
+            }
+        }
+
+        let mut inline = Styling::default();
+
+        for attribute in child.attributes() {
+            let (name, value) = (attribute.name(), attribute.value());
+
+            if !INLINE_PROPERTIES.contains(&name) {
+                diagnostics.push(CapsuleDiagnostic::warning(
+                    format!("'{name}' can't be set on '{tag_name}'"),
+                    CapsuleSpan::from_range(child.document(), attribute.range_qname()),
+                ));
+                continue;
+            }
+
+            if apply_style_property(&mut inline, name, value).is_err() {
+                diagnostics.push(CapsuleDiagnostic::warning(
+                    format!("bad {name} property: '{value}'"),
+                    CapsuleSpan::of_attribute(&child, name),
+                ));
+            }
+        }
+
+        style.color = inline.color.or(style.color);
+        style.font_size = inline.font_size.or(style.font_size);
+        if !inline.font_family.0.is_empty() {
+            style.font_family = inline.font_family;
+        }
+
+        parse_runs(child, &style, runs, diagnostics);
+    }
+}
+
+/// Returns the cleaned up runs of a `<text>`, or reports a diagnostic and
+/// returns none if it has no text, like [`required_text`]
+fn required_runs(node: Node, diagnostics: &mut Vec<CapsuleDiagnostic>) -> Vec<TextRun> {
+    let mut runs = Vec::new();
+    parse_runs(node, &RunStyle::default(), &mut runs, diagnostics);
+
+    if runs.is_empty() {
+        diagnostics.push(CapsuleDiagnostic::warning(
+            format!("'{}' has no text", node.tag_name().name()),
+            CapsuleSpan::of_node(&node),
+        ));
+    }
+
+    clean_runs(&runs)
 }
 
 #[must_use]
-fn parse_capsule_meta(child: Node) -> CapsuleMeta {
+fn parse_capsule_meta(
+    child: Node,
+    dir: &Path,
+    diagnostics: &mut Vec<CapsuleDiagnostic>,
+) -> CapsuleMeta {
     let mut meta = CapsuleMeta::default();
 
     for node in child.children() {
@@ -140,20 +863,56 @@ fn parse_capsule_meta(child: Node) -> CapsuleMeta {
         }
 
         let tag_name = node.tag_name().name();
-        let text = node
-            .text()
-            .map(std::string::ToString::to_string)
-            .map(clean_text);
 
         match tag_name {
             "title" => {
-                meta.title = text.unwrap();
+                meta.title = required_text(node, diagnostics);
             }
             "script" => {
-                meta.scripts.push(CSScript::new(text.unwrap()));
+                meta.scripts
+                    .push(CSScript::new(required_text(node, diagnostics)));
+            }
+            "font" => {
+                let (Some(name), Some(src)) = (node.attribute("name"), node.attribute("src"))
+                else {
+                    diagnostics.push(CapsuleDiagnostic::warning(
+                        "'font' needs a name and a src",
+                        CapsuleSpan::of_node(&node),
+                    ));
+                    continue;
+                };
+
+                let mut font = CSFont::new(name.to_owned(), src.to_owned());
+
+                if let Err(e) = font.load(dir) {
+                    diagnostics.push(CapsuleDiagnostic::warning(
+                        format!("failed to load font '{name}': {e:#}"),
+                        CapsuleSpan::of_attribute(&node, "src"),
+                    ));
+                }
+
+                meta.fonts.push(font);
+            }
+            "style" => {
+                if let Some(text_node) = node.first_child().filter(Node::is_text) {
+                    let offset = text_node.range().start;
+                    meta.stylesheet.append(
+                        text_node.text().unwrap_or_default(),
+                        diagnostics,
+                        |range| {
+                            CapsuleSpan::from_range(
+                                node.document(),
+                                offset + range.start..offset + range.end,
+                            )
+                        },
+                    );
+                }
             }
             _ => {
-                log::warn!("unknown node type: '{tag_name}'");
+                diagnostics.push(CapsuleDiagnostic::warning(
+                    format!("unknown node type: '{tag_name}'"),
+                    CapsuleSpan::of_node(&node),
+                ));
             }
         }
     }
@@ -163,44 +922,86 @@ fn parse_capsule_meta(child: Node) -> CapsuleMeta {
 
 #[must_use]
 #[allow(clippy::too_many_lines)]
-fn parse_capsule_view(view: Node) -> CSView {
-    fn parse_child(child: Node) -> Option<BoxedCapsuleObject> {
+fn parse_capsule_view(
+    view: Node,
+    stylesheet: &Stylesheet,
+    dir: &Path,
+    diagnostics: &mut Vec<CapsuleDiagnostic>,
+) -> CSView {
+    fn parse_child(
+        child: Node,
+        stylesheet: &Stylesheet,
+        dir: &Path,
+        ancestors: &mut Vec<SelectorSubject>,
+        diagnostics: &mut Vec<CapsuleDiagnostic>,
+    ) -> Option<BoxedCapsuleObject> {
         if child.is_text() || child.is_comment() {
             return None;
         }
 
         let tag_name = child.tag_name().name();
-        let child_text = child
-            .text()
-            .map(std::string::ToString::to_string)
-            .map(clean_text);
 
-        // collect children
-        let children = ConcurrentVec::new();
+        // collect id & classes
+        let id = child.attribute("id").map(std::string::ToString::to_string);
+        let classes: Vec<String> = child
+            .attribute("class")
+            .map(|c| c.split_whitespace().map(ToOwned::to_owned).collect())
+            .unwrap_or_default();
 
-        for child in child.children() {
-            let c = parse_child(child);
-            if let Some(c) = c {
-                children.push(c);
-            }
-        }
+        let subject = SelectorSubject {
+            tag: tag_name.to_owned(),
+            id: id.clone(),
+            classes: classes.clone(),
+        };
 
-        // collect styles & events
+        // collect styles & events, inline attributes win over the stylesheet
         let mut style = Styling::default();
         let events = ConcurrentVec::new();
 
-        primitive_attr!(child, style, font_size, u16);
-        dimension_attr!(child, style, width);
-        dimension_attr!(child, style, height);
-        enum_attr!(child, style, align, COAlignItems);
-        enum_attr!(child, style, justify, COJustifyContent);
-        enum_attr!(child, style, flexdir, COFlexDirection);
-        color_attr!(child, style, color);
-        color_attr!(child, style, background_color);
+        stylesheet.cascade(&mut style, ancestors, &subject);
+
+        for attribute in child.attributes() {
+            let (name, value) = (attribute.name(), attribute.value());
+
+            if NON_STYLE_ATTRIBUTES.contains(&name)
+                || (tag_name == "image" && IMAGE_ATTRIBUTES.contains(&name))
+            {
+                continue;
+            }
+
+            match apply_style_property(&mut style, name, value) {
+                Ok(()) => {}
+                Err(StylePropertyError::Unknown) => {
+                    diagnostics.push(CapsuleDiagnostic::warning(
+                        format!("unknown attribute: '{name}'"),
+                        CapsuleSpan::from_range(child.document(), attribute.range_qname()),
+                    ));
+                }
+                Err(StylePropertyError::BadValue) => {
+                    diagnostics.push(CapsuleDiagnostic::warning(
+                        format!("bad {name} property: '{value}'"),
+                        CapsuleSpan::of_attribute(&child, name),
+                    ));
+                }
+            }
+        }
+
         event_attr!(child, events, onclick);
+        event_attr!(child, events, onresize);
 
-        // collect id
-        let id = child.attribute("id").map(std::string::ToString::to_string);
+        // collect children, what's inside text is its runs instead
+        let children = ConcurrentVec::new();
+
+        ancestors.push(subject);
+        if tag_name != "text" {
+            for child in child.children() {
+                let c = parse_child(child, stylesheet, dir, ancestors, diagnostics);
+                if let Some(c) = c {
+                    children.push(c);
+                }
+            }
+        }
+        ancestors.pop();
 
         // clone and create arcs
         let mut style_clone = style.clone();
@@ -209,16 +1010,43 @@ fn parse_capsule_view(view: Node) -> CSView {
         let style_arc = RwLock::new(style).into();
         let events_arc = events.into();
         let id_arc = RwLock::new(id).into();
+        let classes_arc = RwLock::new(classes).into();
 
-        let mut ctx =
-            CapsuleObjectCreationContext::new(children_arc, events_arc, style_arc, id_arc);
+        let mut ctx = CapsuleObjectCreationContext::new(
+            children_arc,
+            events_arc,
+            style_arc,
+            id_arc,
+            classes_arc,
+        );
 
         match tag_name {
             "text" => Some(Arc::new(CSText::new(
-                child_text.as_ref().unwrap().clone(),
+                required_runs(child, diagnostics),
                 ctx,
             ))),
             "obj" => Some(Arc::new(CSObj::new(ctx))),
+            "image" => {
+                let alt = child.attribute("alt").unwrap_or_default().to_owned();
+                let image = CSImage::new(dir.to_owned(), alt, ctx);
+
+                match child.attribute("src") {
+                    Some(src) => {
+                        if let Err(e) = image.set_src(src.to_owned()) {
+                            diagnostics.push(CapsuleDiagnostic::warning(
+                                format!("failed to load image '{src}': {e:#}"),
+                                CapsuleSpan::of_attribute(&child, "src"),
+                            ));
+                        }
+                    }
+                    None => diagnostics.push(CapsuleDiagnostic::warning(
+                        "'image' has no src",
+                        CapsuleSpan::of_node(&child),
+                    )),
+                }
+
+                Some(Arc::new(image))
+            }
             "br" => {
                 style_clone.width = Some(CODimension::Points(0.0));
                 style_clone.height = Some(CODimension::Points(BR_LINE_HEIGHT));
@@ -226,58 +1054,450 @@ fn parse_capsule_view(view: Node) -> CSView {
 
                 Some(Arc::new(CSObj::new(ctx)))
             }
-            "script" => Some(Arc::new(CSScript::new(
-                child_text.as_ref().unwrap().clone(),
-            ))),
+            "script" => Some(Arc::new(CSScript::new(required_text(child, diagnostics)))),
             "view" => Some(Arc::new(CSView::new(ctx))),
             _ => {
-                log::warn!("unknown node type: '{tag_name}'");
+                diagnostics.push(CapsuleDiagnostic::warning(
+                    format!("unknown node type: '{tag_name}'"),
+                    CapsuleSpan::of_node(&child),
+                ));
                 None
             }
         }
     }
 
-    let out = parse_child(view);
-    if out.is_none() {
-        let out = CSView::default();
-        log::error!("view is not a valid element!");
-        return out;
-    }
-    let out = out.as_ref().unwrap().as_any().downcast_ref::<CSView>();
+    let out = parse_child(view, stylesheet, dir, &mut Vec::new(), diagnostics);
+    let out = out
+        .as_ref()
+        .and_then(|out| out.as_any().downcast_ref::<CSView>());
     if out.is_none() {
-        let out = CSView::default();
-        log::error!("view is not a valid view element!");
-        return out;
+        diagnostics.push(CapsuleDiagnostic::error(
+            "view is not a valid view element!",
+            CapsuleSpan::of_node(&view),
+        ));
+        return CSView::default();
     }
 
     out.unwrap().clone()
 }
 
-pub fn parse_capsule(text: &str) -> anyhow::Result<Capsule> {
+/// Parses a capsule document, returning it alongside every non-fatal problem
+/// found while parsing it. Fatal problems are returned as a [`CapsuleDiagnostic`]
+/// error. Images are loaded relative to the working directory.
+pub fn parse_capsule(text: &str) -> anyhow::Result<(Capsule, Vec<CapsuleDiagnostic>)> {
+    parse_capsule_in(text, Path::new(""))
+}
+
+/// Reads and parses the capsule at `path`, with its images loaded relative
+/// to it
+pub fn parse_capsule_file(
+    path: impl AsRef<Path>,
+) -> anyhow::Result<(Capsule, Vec<CapsuleDiagnostic>)> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+
+    parse_capsule_in(&text, path.parent().unwrap_or(Path::new("")))
+}
+
+fn parse_capsule_in(text: &str, dir: &Path) -> anyhow::Result<(Capsule, Vec<CapsuleDiagnostic>)> {
     let mut capsule = Capsule::default();
-    let xml_document = roxmltree::Document::parse(text)?;
+    let mut diagnostics = Vec::new();
+    let xml_document = roxmltree::Document::parse(text).map_err(|e| {
+        CapsuleDiagnostic::error(e.to_string(), CapsuleSpan::from_text_pos(text, e.pos()))
+    })?;
 
     if xml_document.root_element().tag_name().name() != "capsule" {
-        return Err(anyhow::anyhow!("Root node is not of tag capsule"));
+        return Err(CapsuleDiagnostic::error(
+            "Root node is not of tag capsule",
+            CapsuleSpan::of_node(&xml_document.root_element()),
+        )
+        .into());
     }
 
-    let root_children = xml_document.root_element().children();
+    let mut meta = None;
+    let mut view = None;
 
-    for root_child in root_children {
-        if root_child.is_text() {
+    for root_child in xml_document.root_element().children() {
+        if root_child.is_text() || root_child.is_comment() {
             continue;
         }
 
-        if root_child.tag_name().name() != "meta" && root_child.tag_name().name() != "view" {
-            return Err(anyhow::anyhow!("Sub-root node is not of tag meta or view"));
+        match root_child.tag_name().name() {
+            "meta" => meta = Some(root_child),
+            "view" => view = Some(root_child),
+            _ => {
+                return Err(CapsuleDiagnostic::error(
+                    "Sub-root node is not of tag meta or view",
+                    CapsuleSpan::of_node(&root_child),
+                )
+                .into());
+            }
         }
+    }
 
-        if root_child.tag_name().name() == "meta" {
-            capsule.meta = parse_capsule_meta(root_child);
-        } else {
-            capsule.view = parse_capsule_view(root_child);
-        }
+    // the meta has to be parsed first, the view depends on its stylesheet
+    if let Some(meta) = meta {
+        capsule.meta = parse_capsule_meta(meta, dir, &mut diagnostics);
+    }
+
+    if let Some(view) = view {
+        capsule.view = parse_capsule_view(view, &capsule.meta.stylesheet, dir, &mut diagnostics);
+    }
+
+    Ok((capsule, diagnostics))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capsule::{diagnostic::CapsuleDiagnosticSeverity, obj::CapsuleObject};
+    use macroquad::math::Vec2;
+
+    #[test]
+    fn test_capsule_parses_cleanly() {
+        let (_, diagnostics) = parse_capsule(include_str!("../../test.capsule")).unwrap();
+
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    }
+
+    #[test]
+    fn bad_attribute_reports_span() {
+        let (_, diagnostics) = parse_capsule(
+            "<capsule>\n    <view>\n        <obj width=\"wide\" />\n    </view>\n</capsule>",
+        )
+        .unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.severity, CapsuleDiagnosticSeverity::Warning);
+        assert_eq!(diagnostic.message, "bad width property: 'wide'");
+        assert_eq!((diagnostic.span.line, diagnostic.span.column), (3, 21));
+        assert_eq!(diagnostic.span.end - diagnostic.span.start, "wide".len());
+    }
+
+    #[test]
+    fn empty_text_and_unknown_tags_are_reported() {
+        let (_, diagnostics) =
+            parse_capsule("<capsule><view><text/><marquee/></view></capsule>").unwrap();
+
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            ["'text' has no text", "unknown node type: 'marquee'"]
+        );
+    }
+
+    #[test]
+    fn stylesheet_cascades_under_inline_attributes() {
+        let (capsule, diagnostics) = parse_capsule(
+            "<capsule>
+                <view>
+                    <obj class=\"card\"><text font_size=\"10\">a</text></obj>
+                    <text>b</text>
+                </view>
+                <meta><style>.card text { font_size: 30; color: red } text { font_size: 20 }</style></meta>
+            </capsule>",
+        )
+        .unwrap();
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+
+        let children = capsule.view.base().children_vec();
+        let card_text = children[0].base().children_vec()[0].base();
+        let text = children[1].base();
+
+        assert_eq!(*children[0].base().classes.read(), ["card"]);
+        assert_eq!(card_text.style.read().font_size, Some(10));
+        assert!(card_text.style.read().color.is_some());
+        assert_eq!(text.style.read().font_size, Some(20));
+        assert!(text.style.read().color.is_none());
     }
 
-    Ok(capsule)
+    #[test]
+    fn unknown_attributes_and_bad_declarations_are_reported() {
+        let (_, diagnostics) = parse_capsule(
+            "<capsule>\n<meta><style>\ntext { font_size: big }</style></meta>\n<view colour=\"red\" flex_grow=\"-1\" /></capsule>",
+        )
+        .unwrap();
+
+        assert_eq!(diagnostics[0].message, "bad font_size property: 'big'");
+        assert_eq!(
+            (diagnostics[0].span.line, diagnostics[0].span.column),
+            (3, 8)
+        );
+        assert_eq!(diagnostics[1].message, "unknown attribute: 'colour'");
+        assert_eq!(
+            (diagnostics[1].span.line, diagnostics[1].span.column),
+            (4, 7)
+        );
+        assert_eq!(diagnostics[2].message, "bad flex_grow property: '-1'");
+    }
+
+    #[test]
+    fn dimensions_with_units() {
+        assert_eq!(try_parse_dimension("12px"), Some(CODimension::Points(12.0)));
+        assert_eq!(try_parse_dimension("1.5em"), Some(CODimension::Em(1.5)));
+        assert_eq!(try_parse_dimension("2rem"), Some(CODimension::Rem(2.0)));
+        assert_eq!(try_parse_dimension("50vw"), Some(CODimension::Vw(50.0)));
+        assert_eq!(try_parse_dimension("10pt"), None);
+
+        let calc = try_parse_dimension("calc(100% - 20px + 1e1 -2em)").unwrap();
+        assert_eq!(
+            calc,
+            CODimension::Calc(COCalc {
+                points: -10.0,
+                percent: 1.0,
+                em: -2.0,
+                ..COCalc::default()
+            })
+        );
+        assert_eq!(calc.as_text(), "calc(-10 + 100% - 2em)");
+        assert_eq!(try_parse_dimension(&calc.as_text()), Some(calc));
+        assert_eq!(try_parse_dimension("calc(auto + 2)"), None);
+        assert_eq!(try_parse_dimension("calc(1 * 2)"), None);
+    }
+
+    #[test]
+    fn edge_shorthands() {
+        let points = CODimension::Points;
+
+        assert_eq!(try_parse_edges("4"), Some(COEdges::all(points(4.0))));
+        assert_eq!(
+            try_parse_edges("4 calc(50% - 2px) 1em"),
+            Some(COEdges {
+                top: points(4.0),
+                right: try_parse_dimension("calc(50% - 2px)").unwrap(),
+                bottom: CODimension::Em(1.0),
+                left: try_parse_dimension("calc(50% - 2px)").unwrap(),
+            })
+        );
+        assert_eq!(try_parse_edges("1 2 3 4 5"), None);
+        assert_eq!(try_parse_ratio("16 / 8"), Some(2.0));
+        assert_eq!(try_parse_ratio("1/0"), None);
+        assert_eq!(try_parse_edges("calc(1 + 2"), None);
+        assert_eq!(try_parse_edges("4 8").unwrap().as_text(), "4 8");
+        assert_eq!(try_parse_edges("1 2 3 2").unwrap().as_text(), "1 2 3");
+    }
+
+    #[test]
+    fn decorations() {
+        let points = CODimension::Points;
+
+        assert_eq!(
+            try_parse_corners("4 50%"),
+            Some(COCorners {
+                top_left: points(4.0),
+                top_right: CODimension::Percent(0.5),
+                bottom_right: points(4.0),
+                bottom_left: CODimension::Percent(0.5),
+            })
+        );
+        assert_eq!(try_parse_corners("1 2 3 2").unwrap().as_text(), "1 2 3");
+        assert_eq!(try_parse_corners("1 2 3 4 5"), None);
+
+        let shadow = try_parse_box_shadow("0 2 8 #00000080, red 1 1").unwrap();
+        assert_eq!(
+            shadow.0,
+            [
+                COShadow {
+                    offset_x: points(0.0),
+                    offset_y: points(2.0),
+                    blur: points(8.0),
+                    spread: points(0.0),
+                    color: COColor::from_rgba(0, 0, 0, 128),
+                },
+                COShadow {
+                    offset_x: points(1.0),
+                    offset_y: points(1.0),
+                    blur: points(0.0),
+                    spread: points(0.0),
+                    color: try_parse_color("red").unwrap(),
+                },
+            ]
+        );
+        assert_eq!(try_parse_box_shadow(&shadow.as_text()), Some(shadow));
+        assert_eq!(try_parse_box_shadow("none"), Some(COBoxShadow::default()));
+        assert_eq!(try_parse_box_shadow("1"), None);
+        assert_eq!(try_parse_box_shadow("1 2 3 4 5"), None);
+        assert_eq!(try_parse_box_shadow("red 1 2 blue"), None);
+    }
+
+    #[test]
+    fn inline_runs() {
+        let (capsule, diagnostics) = parse_capsule(
+            r#"<capsule>
+    <view>
+        <text>
+            Price: <b>$10</b>,
+            <span color="red" font_size="8"><i>was</i><s> $12</s></span>
+            <b width="3"><q>gone</q><b font_size="big">!</b></b>
+        </text>
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+
+        assert_eq!(
+            messages,
+            [
+                "'width' can't be set on 'b'",
+                "unknown node type: 'q'",
+                "bad font_size property: 'big'"
+            ]
+        );
+
+        let text = capsule.view.base().children_vec()[0].clone();
+        let text = text.as_any().downcast_ref::<CSText>().unwrap();
+        let bold = RunStyle {
+            bold: true,
+            ..RunStyle::default()
+        };
+        let span = RunStyle {
+            color: try_parse_color("red"),
+            font_size: Some(8),
+            ..RunStyle::default()
+        };
+        let run = |text: &str, style: &RunStyle| TextRun {
+            text: text.to_owned(),
+            style: style.clone(),
+        };
+
+        assert_eq!(
+            *text.runs.read(),
+            [
+                run("Price: ", &RunStyle::default()),
+                run("$10", &bold),
+                run(",\n", &RunStyle::default()),
+                run(
+                    "was",
+                    &RunStyle {
+                        italic: true,
+                        ..span.clone()
+                    }
+                ),
+                run(" ", &span),
+                run(
+                    "$12",
+                    &RunStyle {
+                        strikethrough: true,
+                        ..span
+                    }
+                ),
+                run("\n", &RunStyle::default()),
+                run("!", &bold),
+            ]
+        );
+        assert_eq!(text.text(), "Price: $10,\nwas $12\n!");
+    }
+
+    #[test]
+    fn font_families() {
+        let family = try_parse_font_family(" 'Brand Sans', \"Brand\",default").unwrap();
+        assert_eq!(family.0, ["Brand Sans", "Brand", "default"]);
+        assert_eq!(try_parse_font_family(&family.as_text()), Some(family));
+        assert_eq!(
+            try_parse_font_family("inherit"),
+            Some(COFontFamily::default())
+        );
+        assert_eq!(try_parse_font_family("a,,b"), None);
+        assert_eq!(try_parse_font_family("'a"), None);
+
+        let (capsule, diagnostics) = parse_capsule(
+            "<capsule>\n    <meta>\n        <font name=\"Brand\" src=\"missing.ttf\" />\n        <font name=\"Other\" />\n    </meta>\n    <view />\n</capsule>",
+        )
+        .unwrap();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert!(messages[0].starts_with("failed to load font 'Brand'"));
+        assert_eq!(diagnostics[0].span.line, 3);
+        assert_eq!(messages[1], "'font' needs a name and a src");
+
+        // it's kept to be serialized, but nothing can be drawn with it
+        assert_eq!(
+            capsule.meta.fonts,
+            [CSFont::new("Brand".to_owned(), "missing.ttf".to_owned())]
+        );
+        assert_eq!(
+            try_parse_font_family("Brand")
+                .unwrap()
+                .resolve(&capsule.meta.fonts),
+            None
+        );
+    }
+
+    #[test]
+    fn images_load_relative_to_the_capsule() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/images.capsule");
+        let (capsule, diagnostics) = parse_capsule_file(path).unwrap();
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+
+        let children = capsule.view.base().children_vec();
+        let image = children[0].as_any().downcast_ref::<CSImage>().unwrap();
+        assert_eq!(*image.src.read(), "images/checker.png");
+        assert_eq!(image.natural_size(), Some(Vec2::new(32.0, 16.0)));
+
+        // the working directory isn't where the images are
+        let (capsule, diagnostics) = parse_capsule(
+            "<capsule>\n    <view>\n        <image src=\"images/checker.png\" alt=\"checker\" />\n        <image />\n    </view>\n</capsule>",
+        )
+        .unwrap();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert!(messages[0].starts_with("failed to load image 'images/checker.png'"));
+        assert_eq!(diagnostics[0].span.line, 3);
+        assert_eq!(messages[1], "'image' has no src");
+
+        let image = capsule.view.base().children_vec()[0].clone();
+        let image = image.as_any().downcast_ref::<CSImage>().unwrap();
+        assert_eq!(image.natural_size(), None);
+        assert_eq!(*image.alt.read(), "checker");
+    }
+
+    #[test]
+    fn transforms() {
+        let transform =
+            try_parse_transform("translate(10, calc(50% - 2)) rotate(0.5turn) scale(2)");
+
+        assert_eq!(
+            transform.unwrap().0,
+            [
+                COTransformFunction::Translate(
+                    CODimension::Points(10.0),
+                    try_parse_dimension("calc(50% - 2)").unwrap()
+                ),
+                COTransformFunction::Rotate(180.0),
+                COTransformFunction::Scale(2.0, 2.0),
+            ]
+        );
+        assert_eq!(try_parse_transform("none"), Some(COTransform::default()));
+        assert_eq!(try_parse_transform("skew(10deg)"), None);
+        assert_eq!(try_parse_transform("scale(1, 2, 3)"), None);
+        assert_eq!(
+            try_parse_transform_origin("right 4").unwrap().as_text(),
+            "100% 4"
+        );
+        assert_eq!(
+            try_parse_transform("rotate(90) translate(1)")
+                .unwrap()
+                .as_text(),
+            "rotate(90deg) translate(1, 0)"
+        );
+    }
+
+    #[test]
+    fn structural_errors_are_diagnostics() {
+        let err = parse_capsule("<capsule>\n<body/>\n</capsule>").unwrap_err();
+        let diagnostic = err.downcast_ref::<CapsuleDiagnostic>().unwrap();
+
+        assert_eq!(diagnostic.severity, CapsuleDiagnosticSeverity::Error);
+        assert_eq!(diagnostic.span.line, 2);
+
+        let err = parse_capsule("<capsule>\n  <view>\n</capsule>").unwrap_err();
+        let diagnostic = err.downcast_ref::<CapsuleDiagnostic>().unwrap();
+
+        assert_eq!(diagnostic.span.line, 3);
+    }
 }
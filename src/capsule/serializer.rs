@@ -0,0 +1,591 @@
+use std::fmt::Write;
+
+use crate::{
+    capsule::{
+        Capsule,
+        obj::{CapsuleMeta, CapsuleObject},
+        objs::{
+            image::CSImage,
+            script::CSScript,
+            text::{CSText, TextRun},
+            view::CSView,
+        },
+        stylesheet::{SelectorSubject, Stylesheet},
+    },
+    layout::styling::Styling,
+};
+
+const INDENT: &str = "    ";
+
+macro_rules! enum_attr {
+    ($attrs: ident, $style: ident, $base: ident, $name: ident) => {
+        if $style.$name != $base.$name {
+            $attrs.push((stringify!($name), $style.$name.as_ref().to_owned()));
+        }
+    };
+}
+
+/// Properties unset at runtime but set by the stylesheet are written with
+/// the value that unsets them again, so the stylesheet's doesn't come back
+macro_rules! dimension_attr {
+    ($attrs: ident, $style: ident, $base: ident, $name: ident) => {
+        if $style.$name != $base.$name {
+            $attrs.push((
+                stringify!($name),
+                $style
+                    .$name
+                    .map_or_else(|| "auto".to_owned(), |value| value.as_text()),
+            ));
+        }
+    };
+}
+
+macro_rules! edges_attr {
+    ($attrs: ident, $style: ident, $base: ident, $name: ident) => {
+        if $style.$name != $base.$name {
+            $attrs.push((stringify!($name), $style.$name.as_text()));
+        }
+    };
+}
+
+macro_rules! text_attr {
+    ($attrs: ident, $style: ident, $base: ident, $name: ident) => {
+        if $style.$name != $base.$name {
+            $attrs.push((stringify!($name), $style.$name.as_text()));
+        }
+    };
+}
+
+macro_rules! color_attr {
+    ($attrs: ident, $style: ident, $base: ident, $name: ident) => {
+        if $style.$name != $base.$name {
+            $attrs.push((
+                stringify!($name),
+                $style
+                    .$name
+                    .map_or_else(|| "none".to_owned(), |value| value.as_str()),
+            ));
+        }
+    };
+}
+
+macro_rules! primitive_attr {
+    ($attrs: ident, $style: ident, $base: ident, $name: ident) => {
+        if $style.$name != $base.$name {
+            $attrs.push((stringify!($name), $style.$name.to_string()));
+        }
+    };
+}
+
+macro_rules! optional_attr {
+    ($attrs: ident, $style: ident, $base: ident, $name: ident) => {
+        if $style.$name != $base.$name {
+            $attrs.push((
+                stringify!($name),
+                $style
+                    .$name
+                    .map_or_else(|| "auto".to_owned(), |value| value.to_string()),
+            ));
+        }
+    };
+}
+
+#[must_use]
+pub fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Returns the attributes needed to turn `base` into `style`, skipping the
+/// ones that already have the same value in both
+#[must_use]
+pub fn styling_attributes(style: &Styling, base: &Styling) -> Vec<(&'static str, String)> {
+    let mut attrs = Vec::new();
+
+    optional_attr!(attrs, style, base, font_size);
+    text_attr!(attrs, style, base, font_family);
+    dimension_attr!(attrs, style, base, width);
+    dimension_attr!(attrs, style, base, height);
+    dimension_attr!(attrs, style, base, min_width);
+    dimension_attr!(attrs, style, base, max_width);
+    dimension_attr!(attrs, style, base, min_height);
+    dimension_attr!(attrs, style, base, max_height);
+    optional_attr!(attrs, style, base, aspect_ratio);
+    enum_attr!(attrs, style, base, object_fit);
+    enum_attr!(attrs, style, base, align);
+    enum_attr!(attrs, style, base, justify);
+    enum_attr!(attrs, style, base, flexdir);
+    enum_attr!(attrs, style, base, flex_wrap);
+    enum_attr!(attrs, style, base, align_self);
+    enum_attr!(attrs, style, base, align_content);
+    primitive_attr!(attrs, style, base, flex_grow);
+    optional_attr!(attrs, style, base, flex_shrink);
+    dimension_attr!(attrs, style, base, flex_basis);
+    color_attr!(attrs, style, base, color);
+    color_attr!(attrs, style, base, background_color);
+    edges_attr!(attrs, style, base, margin);
+    edges_attr!(attrs, style, base, padding);
+    edges_attr!(attrs, style, base, border_width);
+    color_attr!(attrs, style, base, border_color);
+    enum_attr!(attrs, style, base, border_style);
+    edges_attr!(attrs, style, base, border_radius);
+    text_attr!(attrs, style, base, box_shadow);
+    enum_attr!(attrs, style, base, position);
+    dimension_attr!(attrs, style, base, top);
+    dimension_attr!(attrs, style, base, right);
+    dimension_attr!(attrs, style, base, bottom);
+    dimension_attr!(attrs, style, base, left);
+    optional_attr!(attrs, style, base, z_index);
+    enum_attr!(attrs, style, base, overflow);
+    enum_attr!(attrs, style, base, display);
+    enum_attr!(attrs, style, base, visibility);
+    text_attr!(attrs, style, base, grid_template_columns);
+    text_attr!(attrs, style, base, grid_template_rows);
+    dimension_attr!(attrs, style, base, row_gap);
+    dimension_attr!(attrs, style, base, column_gap);
+    text_attr!(attrs, style, base, grid_column);
+    text_attr!(attrs, style, base, grid_row);
+    text_attr!(attrs, style, base, transform);
+    text_attr!(attrs, style, base, transform_origin);
+
+    attrs
+}
+
+fn write_open_tag(out: &mut String, depth: usize, tag: &str, attrs: &[(String, String)]) {
+    let _ = write!(out, "{}<{tag}", INDENT.repeat(depth));
+
+    for (name, value) in attrs {
+        let _ = write!(out, " {name}=\"{}\"", escape_xml(value));
+    }
+}
+
+/// Escapes `text` and indents every line after the first one by `depth`,
+/// [`clean_text`](crate::capsule::parser::clean_text) strips that indentation again when parsing
+fn text_block(text: &str, depth: usize) -> String {
+    escape_xml(text).replace('\n', &format!("\n{}", INDENT.repeat(depth)))
+}
+
+/// Drops the indentation [`text_block`] gave to empty lines
+fn unindent_blank_lines(block: &str) -> String {
+    block
+        .split('\n')
+        .map(|line| if line.trim().is_empty() { "" } else { line })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Writes `runs` like [`text_block`] does, each in the elements that give
+/// it its style
+fn runs_block(runs: &[TextRun], depth: usize) -> String {
+    let mut out = String::new();
+
+    for run in runs {
+        let style = &run.style;
+        let mut inline = Styling::default();
+        inline.color = style.color;
+        inline.font_size = style.font_size;
+        inline.font_family = style.font_family.clone();
+
+        let attrs: Vec<_> = styling_attributes(&inline, &Styling::default())
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value))
+            .collect();
+        let tags: Vec<_> = [
+            (!attrs.is_empty(), "span"),
+            (style.bold, "b"),
+            (style.italic, "i"),
+            (style.underline, "u"),
+            (style.strikethrough, "s"),
+        ]
+        .into_iter()
+        .filter_map(|(set, tag)| set.then_some(tag))
+        .collect();
+
+        for tag in &tags {
+            write_open_tag(&mut out, 0, tag, if *tag == "span" { &attrs } else { &[] });
+            out.push('>');
+        }
+
+        out.push_str(&text_block(&run.text, depth));
+
+        for tag in tags.iter().rev() {
+            let _ = write!(out, "</{tag}>");
+        }
+    }
+
+    out
+}
+
+/// Writes `text` right inside the tags, so it parses back the same without
+/// relying on blank lines around it being dropped
+fn write_text_element(out: &mut String, depth: usize, tag: &str, text: &str) {
+    let _ = writeln!(
+        out,
+        "{}<{tag}>{}</{tag}>",
+        INDENT.repeat(depth),
+        unindent_blank_lines(&text_block(text, depth + 1))
+    );
+}
+
+fn serialize_meta(out: &mut String, meta: &CapsuleMeta) {
+    let _ = writeln!(out, "{INDENT}<meta>");
+
+    // an empty title is reported when parsing
+    if !meta.title.is_empty() {
+        write_text_element(out, 2, "title", &meta.title);
+    }
+
+    for font in &meta.fonts {
+        write_open_tag(
+            out,
+            2,
+            "font",
+            &[
+                ("name".to_owned(), font.name.clone()),
+                ("src".to_owned(), font.src.clone()),
+            ],
+        );
+        let _ = writeln!(out, " />");
+    }
+
+    if !meta.stylesheet.is_empty() {
+        write_text_element(out, 2, "style", meta.stylesheet.to_string().trim_end());
+    }
+
+    for script in &meta.scripts {
+        write_text_element(out, 2, "script", &script.code);
+    }
+
+    let _ = writeln!(out, "{INDENT}</meta>");
+}
+
+fn serialize_object(
+    out: &mut String,
+    depth: usize,
+    object: &dyn CapsuleObject,
+    stylesheet: &Stylesheet,
+    ancestors: &mut Vec<SelectorSubject>,
+) {
+    if let Some(script) = object.as_any().downcast_ref::<CSScript>() {
+        write_text_element(out, depth, "script", &script.code);
+        return;
+    }
+
+    let base = object.base();
+    let text = object
+        .as_any()
+        .downcast_ref::<CSText>()
+        .map(|t| t.runs.read().clone());
+    let image = object.as_any().downcast_ref::<CSImage>();
+    let tag = if text.is_some() {
+        "text"
+    } else if image.is_some() {
+        "image"
+    } else if object.as_any().is::<CSView>() {
+        "view"
+    } else {
+        "obj"
+    };
+
+    let subject = SelectorSubject {
+        tag: tag.to_owned(),
+        id: base.id.read().clone(),
+        classes: base.classes.read().clone(),
+    };
+
+    // only write what the stylesheet doesn't already provide
+    let mut cascaded = Styling::default();
+    stylesheet.cascade(&mut cascaded, ancestors, &subject);
+
+    let mut attrs = Vec::new();
+
+    if let Some(id) = &subject.id {
+        attrs.push(("id".to_owned(), id.clone()));
+    }
+
+    if !subject.classes.is_empty() {
+        attrs.push(("class".to_owned(), subject.classes.join(" ")));
+    }
+
+    if let Some(image) = image {
+        let src = image.src.read();
+        if !src.is_empty() {
+            attrs.push(("src".to_owned(), src.clone()));
+        }
+
+        let alt = image.alt.read();
+        if !alt.is_empty() {
+            attrs.push(("alt".to_owned(), alt.clone()));
+        }
+    }
+
+    attrs.extend(
+        styling_attributes(&base.style.read(), &cascaded)
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value)),
+    );
+
+    for event in base.events.iter() {
+        attrs.push(event.map(|e| (e.name.clone(), e.callback.clone())));
+    }
+
+    write_open_tag(out, depth, tag, &attrs);
+
+    let children = base.children_vec();
+
+    if children.is_empty() {
+        match text {
+            Some(text) => {
+                let _ = writeln!(
+                    out,
+                    ">{}</{tag}>",
+                    unindent_blank_lines(&runs_block(&text, depth + 1))
+                );
+            }
+            None => {
+                let _ = writeln!(out, " />");
+            }
+        }
+        return;
+    }
+
+    out.push('>');
+    if let Some(text) = text {
+        out.push_str(&runs_block(&text, depth + 1));
+    }
+    out.push('\n');
+
+    ancestors.push(subject);
+    for child in &children {
+        serialize_object(out, depth + 1, child.as_ref(), stylesheet, ancestors);
+    }
+    ancestors.pop();
+
+    let _ = writeln!(out, "{}</{tag}>", INDENT.repeat(depth));
+}
+
+/// Serializes the live capsule tree back into canonical `.capsule` XML.
+/// Parsing the output yields an equivalent capsule.
+#[must_use]
+pub fn serialize_capsule(capsule: &Capsule) -> String {
+    let mut out = String::new();
+
+    out.push_str("<capsule>\n");
+    serialize_meta(&mut out, &capsule.meta);
+    serialize_object(
+        &mut out,
+        1,
+        &capsule.view,
+        &capsule.meta.stylesheet,
+        &mut Vec::new(),
+    );
+    out.push_str("</capsule>\n");
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        capsule::parser::{parse_capsule, parse_capsule_file},
+        layout::capsule::{color::RED, dimension::CODimension},
+    };
+
+    const SOURCE: &str = r##"<capsule>
+    <meta>
+        <title>round &amp; trip</title>
+        <style>
+            .row text { color: #ff00008f }
+            #row { justify: space_between }
+        </style>
+        <script>
+            function onclick(obj, btn: number)
+            obj.text = `a &lt; b`
+            end
+        </script>
+    </meta>
+    <view flexdir="column">
+        <text onclick="onclick" background_color="#ff00008f" color="green" font_size="32">hello,
+            world!!</text>
+        <obj id="row" class="row wide" padding="4 8" border_width="1" border_color="red" overflow="scroll">
+            <obj width="50%" height="20" flex_grow="1" flex_basis="2em" background_color="green" transform="rotate(-10deg) scale(1.5, 1)" transform_origin="left top" />
+            <br />
+            <text>hello, world! b5</text>
+            <obj position="absolute" top="-4" right="10%" z_index="3" width="8" aspect_ratio="16/9" max_height="1rem" visibility="hidden" />
+            <obj display="none" />
+        </obj>
+        <text>Price: <b>$10</b>,
+            <span color="red" font_size="8"><i>was</i> <s>$12 &amp; up</s></span></text>
+    </view>
+</capsule>"##;
+
+    #[test]
+    fn round_trip_is_lossless() {
+        let (capsule, _) = parse_capsule(SOURCE).unwrap();
+        let serialized = serialize_capsule(&capsule);
+        let (reparsed, diagnostics) = parse_capsule(&serialized).unwrap();
+
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(serialize_capsule(&reparsed), serialized);
+        assert_eq!(reparsed.meta.title, "round & trip");
+        assert_eq!(reparsed.meta.scripts[0].code, capsule.meta.scripts[0].code);
+        assert_eq!(reparsed.meta.stylesheet, capsule.meta.stylesheet);
+    }
+
+    #[test]
+    fn indented_text_round_trips() {
+        let source = r#"<capsule>
+    <meta>
+        <title>indented</title>
+        <script>
+            function noop()
+            end
+        </script>
+    </meta>
+    <view>
+        <text>
+            one
+
+            two
+        </text>
+        <text>
+            Price: <b>$10</b>,
+            <s>$12</s>
+        </text>
+    </view>
+</capsule>"#;
+        let (capsule, _) = parse_capsule(source).unwrap();
+        let serialized = serialize_capsule(&capsule);
+        let (reparsed, diagnostics) = parse_capsule(&serialized).unwrap();
+        let runs = |capsule: &Capsule| -> Vec<_> {
+            capsule
+                .view
+                .base()
+                .children_vec()
+                .iter()
+                .map(|text| {
+                    let text = text.as_any().downcast_ref::<CSText>().unwrap();
+                    text.runs.read().clone()
+                })
+                .collect()
+        };
+
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(capsule.meta.scripts[0].code, "function noop()\nend");
+        assert_eq!(reparsed.meta.scripts[0].code, capsule.meta.scripts[0].code);
+        assert_eq!(runs(&reparsed), runs(&capsule));
+        assert!(serialized.contains("        <text>one\n\n            two</text>"));
+    }
+
+    #[test]
+    fn fonts_are_serialized() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/fonts.capsule");
+        let (capsule, _) = parse_capsule_file(path).unwrap();
+        let serialized = serialize_capsule(&capsule);
+
+        assert!(
+            serialized.contains(
+                r#"<font name="Dancing Script" src="fonts/DancingScript-Regular.ttf" />"#
+            )
+        );
+        assert!(serialized.contains(r#"<text font_family="Missing, Dancing Script" width="150">"#));
+    }
+
+    #[test]
+    fn runtime_changes_are_serialized() {
+        let (capsule, _) = parse_capsule(SOURCE).unwrap();
+        let row = capsule.view.base().children_vec()[1].clone();
+        let label = row.base().children_vec()[2].clone();
+
+        label
+            .as_any()
+            .downcast_ref::<CSText>()
+            .unwrap()
+            .set_text("changed".to_owned());
+        {
+            let base = row.base();
+            let mut style = base.style.write();
+            style.width = Some(CODimension::Points(120.0));
+            style.color = Some(RED);
+        }
+
+        let serialized = serialize_capsule(&capsule);
+
+        assert!(
+            serialized
+                .contains(r##"<obj id="row" class="row wide" width="120" color="#E62938FF" padding="4 8" border_width="1" border_color="#FF0000FF" overflow="scroll">"##)
+        );
+        assert!(serialized.contains("<text>changed</text>"));
+    }
+
+    #[test]
+    fn unset_properties_stay_unset() {
+        let (capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <style>
+            .box { width: 10; background_color: red; z_index: 2; aspect_ratio: 2 }
+        </style>
+    </meta>
+    <view>
+        <obj class="box" />
+        <image />
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        let object = capsule.view.base().children_vec()[0].clone();
+        {
+            let base = object.base();
+            let mut style = base.style.write();
+            style.width = None;
+            style.background_color = None;
+            style.z_index = None;
+            style.aspect_ratio = None;
+        }
+
+        let serialized = serialize_capsule(&capsule);
+        let (reparsed, diagnostics) = parse_capsule(&serialized).unwrap();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        let object = reparsed.view.base().children_vec()[0].clone();
+        let style = object.base().style.read().clone();
+
+        assert!(serialized.contains(
+            r#"<obj class="box" width="auto" aspect_ratio="auto" background_color="none" z_index="auto" />"#
+        ));
+        assert!(serialized.contains("<image />"));
+        assert!(!serialized.contains("<title>"));
+        // the source had no src either
+        assert_eq!(messages, ["'image' has no src"]);
+        assert_eq!(style.width, Some(CODimension::Auto));
+        assert_eq!(style.background_color, None);
+        assert_eq!(style.z_index, None);
+        assert_eq!(style.aspect_ratio, None);
+    }
+
+    #[test]
+    fn runs_are_serialized() {
+        let (capsule, _) = parse_capsule(SOURCE).unwrap();
+        let serialized = serialize_capsule(&capsule);
+
+        assert!(serialized.contains(
+            "<text>Price: <b>$10</b>,\n            <span font_size=\"8\" color=\"#FF0000FF\"><i>was</i></span>"
+        ));
+        assert!(serialized.contains(
+            "<span font_size=\"8\" color=\"#FF0000FF\"><s>$12 &amp; up</s></span></text>"
+        ));
+    }
+}
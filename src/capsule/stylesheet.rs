@@ -0,0 +1,450 @@
+use std::{fmt::Display, ops::Range};
+
+use crate::{
+    capsule::{
+        diagnostic::{CapsuleDiagnostic, CapsuleSpan},
+        parser::{StylePropertyError, apply_style_property},
+    },
+    layout::styling::Styling,
+};
+
+/// What a selector gets matched against: an element's tag, id and classes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectorSubject {
+    pub tag: String,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+}
+
+/// Ordered like CSS specificity: ids, then classes, then tags
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub struct Specificity {
+    pub ids: u32,
+    pub classes: u32,
+    pub tags: u32,
+}
+
+/// A selector without combinators, e.g. `text.title#main` or `*`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompoundSelector {
+    pub tag: Option<String>,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+}
+
+impl CompoundSelector {
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        fn is_name_char(c: char) -> bool {
+            c.is_ascii_alphanumeric() || c == '_' || c == '-'
+        }
+
+        let mut out = Self::default();
+        let name_end = text.find(['#', '.']).unwrap_or(text.len());
+        let (tag, mut rest) = text.split_at(name_end);
+
+        match tag {
+            "" if rest.is_empty() => return None,
+            "" | "*" => {}
+            _ if tag.chars().all(is_name_char) => out.tag = Some(tag.to_owned()),
+            _ => return None,
+        }
+
+        while let Some(kind) = rest.chars().next() {
+            let name_len = rest[1..].find(['#', '.']).unwrap_or(rest.len() - 1);
+            let name = &rest[1..=name_len];
+
+            if name.is_empty() || !name.chars().all(is_name_char) {
+                return None;
+            }
+
+            if kind == '#' {
+                if out.id.is_some() {
+                    return None;
+                }
+                out.id = Some(name.to_owned());
+            } else {
+                out.classes.push(name.to_owned());
+            }
+
+            rest = &rest[name_len + 1..];
+        }
+
+        Some(out)
+    }
+
+    #[must_use]
+    pub fn matches(&self, subject: &SelectorSubject) -> bool {
+        self.tag.as_ref().is_none_or(|tag| *tag == subject.tag)
+            && self
+                .id
+                .as_ref()
+                .is_none_or(|id| subject.id.as_ref() == Some(id))
+            && self.classes.iter().all(|c| subject.classes.contains(c))
+    }
+}
+
+impl Display for CompoundSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.tag {
+            Some(tag) => write!(f, "{tag}")?,
+            None if self.id.is_none() && self.classes.is_empty() => write!(f, "*")?,
+            None => {}
+        }
+
+        if let Some(id) = &self.id {
+            write!(f, "#{id}")?;
+        }
+
+        for class in &self.classes {
+            write!(f, ".{class}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A chain of compound selectors joined by the descendant combinator,
+/// outermost ancestor first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    pub compounds: Vec<CompoundSelector>,
+}
+
+impl Selector {
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        let compounds = text
+            .split_whitespace()
+            .map(CompoundSelector::parse)
+            .collect::<Option<Vec<_>>>()?;
+
+        if compounds.is_empty() {
+            return None;
+        }
+
+        Some(Self { compounds })
+    }
+
+    #[must_use]
+    pub fn specificity(&self) -> Specificity {
+        let mut out = Specificity::default();
+
+        for compound in &self.compounds {
+            out.ids += u32::from(compound.id.is_some());
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                out.classes += compound.classes.len() as u32;
+            }
+            out.tags += u32::from(compound.tag.is_some());
+        }
+
+        out
+    }
+
+    /// `ancestors` is ordered from the root down to the subject's parent
+    #[must_use]
+    pub fn matches(&self, ancestors: &[SelectorSubject], subject: &SelectorSubject) -> bool {
+        let Some((last, rest)) = self.compounds.split_last() else {
+            return false;
+        };
+
+        if !last.matches(subject) {
+            return false;
+        }
+
+        let mut ancestors = ancestors.iter().rev();
+
+        rest.iter()
+            .rev()
+            .all(|compound| ancestors.any(|a| compound.matches(a)))
+    }
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, compound) in self.compounds.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{compound}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleDeclaration {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleRule {
+    pub selectors: Vec<Selector>,
+    pub declarations: Vec<StyleDeclaration>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stylesheet {
+    pub rules: Vec<StyleRule>,
+}
+
+/// Shrinks `range` so it excludes the whitespace around `source[range]`
+fn trim_range(source: &str, range: Range<usize>) -> Range<usize> {
+    let text = &source[range.clone()];
+    let start = range.start + (text.len() - text.trim_start().len());
+    let end = range.end - (text.len() - text.trim_end().len());
+
+    start..end.max(start)
+}
+
+/// Splits `source[range]` on `separator`, returning the trimmed ranges of
+/// every non-empty piece
+fn split_range(source: &str, range: Range<usize>, separator: char) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut start = range.start;
+
+    for (i, _) in source[range.clone()].match_indices(separator) {
+        out.push(trim_range(source, start..range.start + i));
+        start = range.start + i + 1;
+    }
+    out.push(trim_range(source, start..range.end));
+
+    out.retain(|r| !r.is_empty());
+    out
+}
+
+impl Stylesheet {
+    /// Parses the contents of a `<style>` block and appends its rules,
+    /// `span` maps a byte range of `source` to its location in the capsule
+    pub fn append<F>(&mut self, source: &str, diagnostics: &mut Vec<CapsuleDiagnostic>, span: F)
+    where
+        F: Fn(Range<usize>) -> CapsuleSpan,
+    {
+        // blank out comments so byte offsets stay intact
+        let mut source = source.to_owned();
+        while let Some(start) = source.find("/*") {
+            let end = source[start + 2..]
+                .find("*/")
+                .map_or(source.len(), |i| start + i + 4);
+            let blank = " ".repeat(source[start..end].len());
+            source.replace_range(start..end, &blank);
+        }
+        let source = source.as_str();
+
+        let mut pos = 0;
+
+        while pos < source.len() {
+            let Some(open) = source[pos..].find('{').map(|i| pos + i) else {
+                let rest = trim_range(source, pos..source.len());
+                if !rest.is_empty() {
+                    diagnostics.push(CapsuleDiagnostic::warning("expected '{'", span(rest)));
+                }
+                break;
+            };
+            let Some(close) = source[open..].find('}').map(|i| open + i) else {
+                diagnostics.push(CapsuleDiagnostic::warning(
+                    "unclosed style rule",
+                    span(open..source.len()),
+                ));
+                break;
+            };
+
+            let prelude = pos..open;
+            let body = open + 1..close;
+            pos = close + 1;
+
+            let mut selectors = Vec::new();
+            let mut valid = true;
+
+            for range in split_range(source, prelude.clone(), ',') {
+                if let Some(selector) = Selector::parse(&source[range.clone()]) {
+                    selectors.push(selector);
+                } else {
+                    diagnostics.push(CapsuleDiagnostic::warning(
+                        format!("bad selector: '{}'", &source[range.clone()]),
+                        span(range),
+                    ));
+                    valid = false;
+                }
+            }
+
+            if selectors.is_empty() && valid {
+                diagnostics.push(CapsuleDiagnostic::warning(
+                    "style rule has no selector",
+                    span(trim_range(source, prelude)),
+                ));
+            }
+
+            let mut declarations = Vec::new();
+
+            for range in split_range(source, body, ';') {
+                let text = &source[range.clone()];
+                let Some((name, value)) = text.split_once(':') else {
+                    diagnostics.push(CapsuleDiagnostic::warning(
+                        format!("expected ':' in '{text}'"),
+                        span(range),
+                    ));
+                    continue;
+                };
+                let (name, value) = (name.trim(), value.trim());
+
+                match apply_style_property(&mut Styling::default(), name, value) {
+                    Ok(()) => declarations.push(StyleDeclaration {
+                        name: name.to_owned(),
+                        value: value.to_owned(),
+                    }),
+                    Err(StylePropertyError::Unknown) => {
+                        diagnostics.push(CapsuleDiagnostic::warning(
+                            format!("unknown style property: '{name}'"),
+                            span(range),
+                        ));
+                    }
+                    Err(StylePropertyError::BadValue) => {
+                        diagnostics.push(CapsuleDiagnostic::warning(
+                            format!("bad {name} property: '{value}'"),
+                            span(range),
+                        ));
+                    }
+                }
+            }
+
+            // like CSS, a single bad selector invalidates the whole rule
+            if valid && !selectors.is_empty() {
+                self.rules.push(StyleRule {
+                    selectors,
+                    declarations,
+                });
+            }
+        }
+    }
+
+    /// Applies every rule matching `subject` to `style`, in order of
+    /// ascending specificity. Rules with equal specificity are applied in
+    /// source order, so the later one wins.
+    pub fn cascade(
+        &self,
+        style: &mut Styling,
+        ancestors: &[SelectorSubject],
+        subject: &SelectorSubject,
+    ) {
+        let mut matched: Vec<_> = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter_map(|(i, rule)| {
+                rule.selectors
+                    .iter()
+                    .filter(|s| s.matches(ancestors, subject))
+                    .map(Selector::specificity)
+                    .max()
+                    .map(|specificity| (specificity, i))
+            })
+            .collect();
+        matched.sort_unstable();
+
+        for (_, i) in matched {
+            for declaration in &self.rules[i].declarations {
+                // declarations are validated when parsed
+                let _ = apply_style_property(style, &declaration.name, &declaration.value);
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+impl Display for Stylesheet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for rule in &self.rules {
+            let selectors: Vec<_> = rule.selectors.iter().map(ToString::to_string).collect();
+            writeln!(f, "{} {{", selectors.join(", "))?;
+
+            for declaration in &rule.declarations {
+                writeln!(f, "    {}: {};", declaration.name, declaration.value)?;
+            }
+
+            writeln!(f, "}}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::capsule::color::COColor;
+
+    fn subject(tag: &str, id: Option<&str>, classes: &[&str]) -> SelectorSubject {
+        SelectorSubject {
+            tag: tag.to_owned(),
+            id: id.map(ToOwned::to_owned),
+            classes: classes.iter().map(|c| (*c).to_owned()).collect(),
+        }
+    }
+
+    fn parse(source: &str) -> (Stylesheet, Vec<CapsuleDiagnostic>) {
+        let mut stylesheet = Stylesheet::default();
+        let mut diagnostics = Vec::new();
+        stylesheet.append(source, &mut diagnostics, |_| CapsuleSpan::default());
+        (stylesheet, diagnostics)
+    }
+
+    #[test]
+    fn descendant_selectors_match_any_ancestor() {
+        let selector = Selector::parse(".card text.title").unwrap();
+        let ancestors = [subject("view", None, &[]), subject("obj", None, &["card"])];
+
+        assert!(selector.matches(&ancestors, &subject("text", None, &["title", "big"])));
+        assert!(!selector.matches(&ancestors, &subject("text", None, &[])));
+        assert!(!selector.matches(&ancestors[..1], &subject("text", None, &["title"])));
+        assert_eq!(
+            selector.specificity(),
+            Specificity {
+                ids: 0,
+                classes: 2,
+                tags: 1
+            }
+        );
+    }
+
+    #[test]
+    fn specificity_beats_source_order() {
+        let (stylesheet, diagnostics) = parse(
+            "#main { font_size: 40 }
+            text.big { font_size: 32; color: red }
+            /* later, but less specific */
+            text { font_size: 12; color: blue }",
+        );
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+
+        let mut style = Styling::default();
+        stylesheet.cascade(&mut style, &[], &subject("text", Some("main"), &["big"]));
+
+        assert_eq!(style.font_size, Some(40));
+        assert_eq!(style.color, Some(COColor::from_rgba(255, 0, 0, 255)));
+    }
+
+    #[test]
+    fn bad_rules_are_reported() {
+        let (stylesheet, diagnostics) = parse("text { colour: red; width: wide } te$t { }");
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+
+        assert_eq!(
+            messages,
+            [
+                "unknown style property: 'colour'",
+                "bad width property: 'wide'",
+                "bad selector: 'te$t'"
+            ]
+        );
+        assert_eq!(stylesheet.rules.len(), 1);
+        assert!(stylesheet.rules[0].declarations.is_empty());
+    }
+}
@@ -1,73 +1,222 @@
+use std::sync::Arc;
+
 use macroquad::{
-    input::{MouseButton, is_mouse_button_pressed, mouse_position},
+    input::{MouseButton, is_mouse_button_pressed, mouse_position, mouse_wheel},
     math::Vec2,
 };
 
 use crate::{
     capsule::{
         Capsule,
-        obj::{ArcLock, iter_all_objects},
+        obj::{ArcLock, BoxedCapsuleObject, iter_all_objects},
     },
+    layout::{capsule::overflow::COOverflow, stacking::StackingOrder},
     lua::holder::CapsuleObjectHandle,
 };
 
-pub fn update_events(capsule: &ArcLock<Capsule>) {
-    let is_m1_pressed = is_mouse_button_pressed(MouseButton::Left);
-    let is_m2_pressed = is_mouse_button_pressed(MouseButton::Right);
-    let is_m3_pressed = is_mouse_button_pressed(MouseButton::Middle);
-
-    if is_m1_pressed || is_m2_pressed || is_m3_pressed {
-        let mouse_position = Vec2::from(mouse_position());
-        let mut callbacks = vec![];
-
-        let capsule_read = capsule.read();
-        iter_all_objects(&capsule_read, |o| {
-            let bb = o.map(|o| o.bounding_box());
-
-            if bb.contains(mouse_position) {
-                o.map(|o| {
-                    for e in o
-                        .base()
-                        .events
-                        .iter()
-                        .filter(|e| e.map(|u| u.name == "onclick"))
-                    {
-                        callbacks.push((
-                            e.map(|u| u.callback.clone()),
-                            CapsuleObjectHandle(o.clone()),
-                        ));
-                    }
-                });
-            }
-        });
+/// How far one step of the mouse wheel scrolls
+const SCROLL_STEP: f32 = 40.0;
+
+/// Returns the callbacks `object` registered for `event`, each paired with a
+/// handle to pass to it
+fn event_callbacks(object: &BoxedCapsuleObject, event: &str) -> Vec<(String, CapsuleObjectHandle)> {
+    object
+        .base()
+        .events
+        .iter()
+        .filter(|e| e.map(|u| u.name == event))
+        .map(|e| {
+            (
+                e.map(|u| u.callback.clone()),
+                CapsuleObjectHandle(object.clone()),
+            )
+        })
+        .collect()
+}
 
-        let mut lua = capsule_read.lua.write();
+/// Input objects react to, read from the window by [`update_events`] or
+/// replayed without one
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum InputEvent {
+    /// `button` is 1 for left, 2 for right and 3 for middle
+    Click { position: Vec2, button: u8 },
+    /// `delta` is in mouse wheel steps
+    Scroll { position: Vec2, delta: Vec2 },
+}
+
+/// Scrolls the innermost scrollable object at `position`
+fn scroll(stacking: &StackingOrder, position: Vec2, delta: Vec2) {
+    let target = stacking
+        .hit_path(position)
+        .into_iter()
+        .find(|o| o.base().style.read().overflow == COOverflow::Scroll);
+
+    if let Some(target) = target {
+        let base = target.base();
+        let scroll = *base.scroll.read();
+        base.set_scroll(scroll - delta * SCROLL_STEP);
+    }
+}
 
-        let mut buttons = vec![];
+/// Returns how many `onclick` handlers ran
+fn click(
+    capsule: &ArcLock<Capsule>,
+    stacking: &StackingOrder,
+    position: Vec2,
+    button: u8,
+) -> usize {
+    // only the topmost object and the ones it's inside of get the click,
+    // whatever is underneath doesn't
+    let callbacks: Vec<_> = stacking
+        .hit_path(position)
+        .iter()
+        .flat_map(|o| event_callbacks(o, "onclick"))
+        .collect();
 
-        if is_m1_pressed {
-            buttons.push(1);
+    let capsule_read = capsule.read();
+    let mut lua = capsule_read.lua.write();
+    let mut ran = 0;
+
+    for (callback, handle) in callbacks {
+        // a typo in the capsule shouldn't take the browser down
+        let function = match lua.get_function(&callback) {
+            Ok(function) => function,
+            Err(e) => {
+                log::error!("onclick handler '{callback}' isn't a function: {e}");
+                continue;
+            }
+        };
+
+        match function.call::<()>((handle, button)) {
+            Ok(()) => ran += 1,
+            Err(e) => log::error!("Lua error: {e}"),
         }
-        if is_m2_pressed {
-            buttons.push(2);
+    }
+
+    ran
+}
+
+/// Returns how many handlers `event` ran, scrolling has none
+pub fn dispatch_input(capsule: &ArcLock<Capsule>, event: InputEvent) -> usize {
+    let stacking = StackingOrder::new(&capsule.read());
+
+    match event {
+        InputEvent::Click { position, button } => click(capsule, &stacking, position, button),
+        InputEvent::Scroll { position, delta } => {
+            scroll(&stacking, position, delta);
+            0
         }
-        if is_m3_pressed {
-            buttons.push(3);
+    }
+}
+
+/// Dispatches the input the window got since the last frame
+pub fn update_events(capsule: &ArcLock<Capsule>) {
+    let position = Vec2::from(mouse_position());
+    let (wheel_x, wheel_y) = mouse_wheel();
+
+    if wheel_x != 0.0 || wheel_y != 0.0 {
+        let delta = Vec2::new(wheel_x, wheel_y);
+        dispatch_input(capsule, InputEvent::Scroll { position, delta });
+    }
+
+    for (mouse_button, button) in [
+        (MouseButton::Left, 1),
+        (MouseButton::Right, 2),
+        (MouseButton::Middle, 3),
+    ] {
+        if is_mouse_button_pressed(mouse_button) {
+            dispatch_input(capsule, InputEvent::Click { position, button });
         }
+    }
+}
+
+/// Calls every `onresize` handler with the new size of the viewport, and
+/// returns how many ran
+pub fn dispatch_resize(capsule: &ArcLock<Capsule>) -> usize {
+    let capsule_read = capsule.read();
+    let viewport = capsule_read.viewport;
+    let root: BoxedCapsuleObject = Arc::new(capsule_read.view.clone());
+    let mut callbacks = event_callbacks(&root, "onresize");
+
+    iter_all_objects(&capsule_read, |o| {
+        callbacks.extend(o.map(|o| event_callbacks(o, "onresize")));
+    });
 
-        for (callback, handle) in &callbacks {
-            for btn in &buttons {
-                if let Err(e) = lua
-                    .get_function(callback)
-                    .unwrap()
-                    .call::<()>((handle.clone(), *btn))
-                {
-                    log::error!("Lua error: {e}");
-                }
+    let mut lua = capsule_read.lua.write();
+    let mut ran = 0;
+
+    for (callback, handle) in callbacks {
+        // a typo in the capsule shouldn't take the browser down
+        let function = match lua.get_function(&callback) {
+            Ok(function) => function,
+            Err(e) => {
+                log::error!("onresize handler '{callback}' isn't a function: {e}");
+                continue;
             }
+        };
+
+        match function.call::<()>((handle, viewport.x, viewport.y)) {
+            Ok(()) => ran += 1,
+            Err(e) => log::error!("Lua error: {e}"),
         }
+    }
+
+    ran
+}
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::RwLock;
+
+    use super::*;
+    use crate::{
+        capsule::parser::parse_capsule,
+        layout::{computer::LayoutTree, text::FixedAdvanceMeasurer},
+    };
+
+    #[test]
+    fn missing_handlers_are_skipped() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>typos</title>
+        <script>
+            clicks = 0
+            resizes = 0
+            function on_click(obj, btn: number)
+                clicks += 1
+            end
+            function on_resize(obj, width: number, height: number)
+                resizes += 1
+            end
+            function counts()
+                return clicks, resizes
+            end
+        </script>
+    </meta>
+    <view onclick="on_clik" onresize="on_resise">
+        <obj width="100" height="100" onclick="on_click" onresize="on_resize" />
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(100.0, 100.0);
+        let _tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let capsule = Arc::new(RwLock::new(capsule));
+        Capsule::run_scripts(&capsule.clone());
+
+        let resized = dispatch_resize(&capsule);
+        let clicked = dispatch_input(
+            &capsule,
+            InputEvent::Click {
+                position: Vec2::new(50.0, 50.0),
+                button: 1,
+            },
+        );
+        let counts = capsule.read().lua.write().get_function("counts").unwrap();
 
-        drop(lua);
-        drop(capsule_read);
+        // the typos are skipped, the handlers next to them still run
+        assert_eq!((clicked, resized), (1, 1));
+        assert_eq!(counts.call::<(u32, u32)>(()).unwrap(), (1, 1));
     }
 }
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use stretch::style::AlignContent;
+use strum::{AsRefStr, EnumString};
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, EnumString, AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum COAlignContent {
+    FlexStart,
+    FlexEnd,
+    Center,
+    #[default]
+    Stretch,
+    SpaceBetween,
+    SpaceAround,
+}
+
+impl COAlignContent {
+    #[must_use]
+    pub const fn as_stretch(&self) -> AlignContent {
+        match self {
+            Self::FlexStart => AlignContent::FlexStart,
+            Self::FlexEnd => AlignContent::FlexEnd,
+            Self::Center => AlignContent::Center,
+            Self::Stretch => AlignContent::Stretch,
+            Self::SpaceBetween => AlignContent::SpaceBetween,
+            Self::SpaceAround => AlignContent::SpaceAround,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use stretch::style::AlignSelf;
+use strum::{AsRefStr, EnumString};
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, EnumString, AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum COAlignSelf {
+    #[default]
+    Auto,
+    FlexStart,
+    FlexEnd,
+    Center,
+    Baseline,
+    Stretch,
+}
+
+impl COAlignSelf {
+    #[must_use]
+    pub const fn as_stretch(&self) -> AlignSelf {
+        match self {
+            Self::Auto => AlignSelf::Auto,
+            Self::FlexStart => AlignSelf::FlexStart,
+            Self::FlexEnd => AlignSelf::FlexEnd,
+            Self::Center => AlignSelf::Center,
+            Self::Baseline => AlignSelf::Baseline,
+            Self::Stretch => AlignSelf::Stretch,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, EnumString, AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum COBorderStyle {
+    #[default]
+    Solid,
+    /// dashes twice as long as the border is wide
+    Dashed,
+    /// squares as long as the border is wide
+    Dotted,
+    /// the border keeps its width but isn't drawn
+    None,
+}
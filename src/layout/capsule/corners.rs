@@ -0,0 +1,96 @@
+use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{
+    capsule::dimension::{CODimension, DimensionContext},
+    computed::ComputedCorners,
+};
+
+/// One dimension per corner of a box, used for `border_radius`
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct COCorners {
+    pub top_left: CODimension,
+    pub top_right: CODimension,
+    pub bottom_right: CODimension,
+    pub bottom_left: CODimension,
+}
+
+impl Default for COCorners {
+    fn default() -> Self {
+        Self::all(CODimension::Points(0.0))
+    }
+}
+
+impl COCorners {
+    #[must_use]
+    pub const fn all(value: CODimension) -> Self {
+        Self {
+            top_left: value,
+            top_right: value,
+            bottom_right: value,
+            bottom_left: value,
+        }
+    }
+
+    /// Builds corners from the 1 to 4 values of a CSS-like shorthand, going
+    /// clockwise from the top left, e.g. `4 8` is 4 on the top left and
+    /// bottom right and 8 on the others
+    #[must_use]
+    pub fn from_shorthand(values: &[CODimension]) -> Option<Self> {
+        let (top_left, top_right, bottom_right, bottom_left) = match *values {
+            [all] => (all, all, all, all),
+            [main, anti] => (main, anti, main, anti),
+            [top_left, anti, bottom_right] => (top_left, anti, bottom_right, anti),
+            [top_left, top_right, bottom_right, bottom_left] => {
+                (top_left, top_right, bottom_right, bottom_left)
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        })
+    }
+
+    /// Percentages are relative to the shorter side of the box, so `50%`
+    /// turns a square into a circle
+    #[must_use]
+    pub fn resolve(&self, ctx: &DimensionContext, size: Vec2) -> ComputedCorners {
+        let side = size.x.min(size.y);
+
+        ComputedCorners {
+            top_left: self.top_left.resolve(ctx, side).max(0.0),
+            top_right: self.top_right.resolve(ctx, side).max(0.0),
+            bottom_right: self.bottom_right.resolve(ctx, side).max(0.0),
+            bottom_left: self.bottom_left.resolve(ctx, side).max(0.0),
+        }
+    }
+
+    /// The shortest shorthand that describes these corners
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        let values = if self.top_right != self.bottom_left {
+            vec![
+                self.top_left,
+                self.top_right,
+                self.bottom_right,
+                self.bottom_left,
+            ]
+        } else if self.top_left != self.bottom_right {
+            vec![self.top_left, self.top_right, self.bottom_right]
+        } else if self.top_left != self.top_right {
+            vec![self.top_left, self.top_right]
+        } else {
+            vec![self.top_left]
+        };
+
+        values
+            .iter()
+            .map(CODimension::as_text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
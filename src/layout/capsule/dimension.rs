@@ -2,6 +2,91 @@ use serde::{Deserialize, Serialize};
 use stretch::style::Dimension;
 use strum::{AsRefStr, EnumString};
 
+/// Everything a relative unit may need to be resolved into points
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DimensionContext {
+    pub font_size: f32,
+    pub root_font_size: f32,
+    pub viewport_width: f32,
+    pub viewport_height: f32,
+}
+
+impl DimensionContext {
+    #[must_use]
+    pub const fn with_font_size(self, font_size: f32) -> Self {
+        Self { font_size, ..self }
+    }
+}
+
+/// The sum of the terms of a `calc()` expression, one field per unit
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct COCalc {
+    pub points: f32,
+    /// 0.0 to 1.0, like [`CODimension::Percent`]
+    pub percent: f32,
+    pub em: f32,
+    pub rem: f32,
+    pub vw: f32,
+    pub vh: f32,
+}
+
+impl COCalc {
+    /// Adds `sign * dimension` to the expression, returns `None` for
+    /// dimensions that can't take part in arithmetic
+    pub fn add(&mut self, dimension: CODimension, sign: f32) -> Option<()> {
+        match dimension {
+            CODimension::Points(v) => self.points += sign * v,
+            CODimension::Percent(v) => self.percent += sign * v,
+            CODimension::Em(v) => self.em += sign * v,
+            CODimension::Rem(v) => self.rem += sign * v,
+            CODimension::Vw(v) => self.vw += sign * v,
+            CODimension::Vh(v) => self.vh += sign * v,
+            CODimension::Undefined | CODimension::Auto | CODimension::Calc(_) => return None,
+        }
+
+        Some(())
+    }
+
+    /// Resolves every term but the percentage into points
+    #[must_use]
+    pub fn absolute(&self, ctx: &DimensionContext) -> f32 {
+        self.points
+            + self.em * ctx.font_size
+            + self.rem * ctx.root_font_size
+            + self.vw / 100.0 * ctx.viewport_width
+            + self.vh / 100.0 * ctx.viewport_height
+    }
+
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        let terms = [
+            (self.points, ""),
+            (self.percent * 100.0, "%"),
+            (self.em, "em"),
+            (self.rem, "rem"),
+            (self.vw, "vw"),
+            (self.vh, "vh"),
+        ];
+        let mut out = String::new();
+
+        for (value, unit) in terms.into_iter().filter(|(v, _)| *v != 0.0) {
+            if out.is_empty() {
+                out = format!("{value}{unit}");
+            } else if value < 0.0 {
+                out += &format!(" - {}{unit}", -value);
+            } else {
+                out += &format!(" + {value}{unit}");
+            }
+        }
+
+        if out.is_empty() {
+            out.push('0');
+        }
+
+        format!("calc({out})")
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize, EnumString, AsRefStr)]
 #[serde(rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
@@ -10,16 +95,57 @@ pub enum CODimension {
     Auto,
     Points(f32),
     Percent(f32),
+    /// relative to the object's font size
+    Em(f32),
+    /// relative to the root view's font size
+    Rem(f32),
+    /// percent of the viewport width
+    Vw(f32),
+    /// percent of the viewport height
+    Vh(f32),
+    Calc(COCalc),
 }
 
 impl CODimension {
+    /// Stretch has no notion of mixing percentages with other units, so a
+    /// `calc()` that does only keeps its percentage here and has to be
+    /// fixed up with [`CODimension::resolve`] once the parent's size is known
     #[must_use]
-    pub const fn as_stretch(&self) -> Dimension {
+    pub fn as_stretch(&self, ctx: &DimensionContext) -> Dimension {
         match self {
             Self::Auto => Dimension::Auto,
             Self::Undefined => Dimension::Undefined,
             Self::Percent(v) => Dimension::Percent(*v),
             Self::Points(v) => Dimension::Points(*v),
+            Self::Em(v) => Dimension::Points(v * ctx.font_size),
+            Self::Rem(v) => Dimension::Points(v * ctx.root_font_size),
+            Self::Vw(v) => Dimension::Points(v / 100.0 * ctx.viewport_width),
+            Self::Vh(v) => Dimension::Points(v / 100.0 * ctx.viewport_height),
+            Self::Calc(calc) if calc.percent == 0.0 => Dimension::Points(calc.absolute(ctx)),
+            Self::Calc(calc) => Dimension::Percent(calc.percent),
+        }
+    }
+
+    /// Whether this is a `calc()` mixing percentages with other units
+    #[must_use]
+    pub fn is_mixed_calc(&self, ctx: &DimensionContext) -> bool {
+        matches!(self, Self::Calc(calc) if calc.percent != 0.0 && calc.absolute(ctx) != 0.0)
+    }
+
+    /// Resolves into points, percentages are relative to `parent`.
+    /// `auto` and `undefined` resolve to 0.
+    #[must_use]
+    pub fn resolve(&self, ctx: &DimensionContext, parent: f32) -> f32 {
+        match self {
+            Self::Auto | Self::Undefined => 0.0,
+            Self::Percent(v) => v * parent,
+            Self::Calc(calc) => calc.percent * parent + calc.absolute(ctx),
+            Self::Points(_) | Self::Em(_) | Self::Rem(_) | Self::Vw(_) | Self::Vh(_) => {
+                match self.as_stretch(ctx) {
+                    Dimension::Points(v) => v,
+                    _ => 0.0,
+                }
+            }
         }
     }
 
@@ -30,6 +156,11 @@ impl CODimension {
             Self::Auto => "auto".to_owned(),
             Self::Points(v) => format!("{v}"),
             Self::Percent(v) => format!("{}%", v * 100.0),
+            Self::Em(v) => format!("{v}em"),
+            Self::Rem(v) => format!("{v}rem"),
+            Self::Vw(v) => format!("{v}vw"),
+            Self::Vh(v) => format!("{v}vh"),
+            Self::Calc(calc) => calc.as_text(),
         }
     }
 }
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, EnumString, AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum CODisplay {
+    /// children are laid out with flexbox
+    #[default]
+    Flex,
+    /// children are placed into the cells of `grid_template_columns` and
+    /// `grid_template_rows`
+    Grid,
+    /// the object and its children are taken out of the layout, as if they
+    /// weren't there
+    None,
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use stretch::{geometry::Rect, style::Dimension};
+
+use crate::layout::{
+    capsule::dimension::{CODimension, DimensionContext},
+    computed::ComputedEdges,
+};
+
+/// One dimension per side of a box, used for margins, paddings and borders
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct COEdges {
+    pub top: CODimension,
+    pub right: CODimension,
+    pub bottom: CODimension,
+    pub left: CODimension,
+}
+
+impl Default for COEdges {
+    fn default() -> Self {
+        Self::all(CODimension::Points(0.0))
+    }
+}
+
+impl COEdges {
+    #[must_use]
+    pub const fn all(value: CODimension) -> Self {
+        Self {
+            top: value,
+            right: value,
+            bottom: value,
+            left: value,
+        }
+    }
+
+    /// Builds edges from the 1 to 4 values of a CSS-like shorthand,
+    /// e.g. `4 8` is 4 on top and bottom and 8 on the left and right
+    #[must_use]
+    pub fn from_shorthand(values: &[CODimension]) -> Option<Self> {
+        let (top, right, bottom, left) = match *values {
+            [all] => (all, all, all, all),
+            [vertical, horizontal] => (vertical, horizontal, vertical, horizontal),
+            [top, horizontal, bottom] => (top, horizontal, bottom, horizontal),
+            [top, right, bottom, left] => (top, right, bottom, left),
+            _ => return None,
+        };
+
+        Some(Self {
+            top,
+            right,
+            bottom,
+            left,
+        })
+    }
+
+    #[must_use]
+    pub fn as_stretch(&self, ctx: &DimensionContext) -> Rect<Dimension> {
+        Rect {
+            start: self.left.as_stretch(ctx),
+            end: self.right.as_stretch(ctx),
+            top: self.top.as_stretch(ctx),
+            bottom: self.bottom.as_stretch(ctx),
+        }
+    }
+
+    /// Like CSS, percentages on every side are relative to the parent's width
+    #[must_use]
+    pub fn resolve(&self, ctx: &DimensionContext, parent_width: f32) -> ComputedEdges {
+        ComputedEdges {
+            top: self.top.resolve(ctx, parent_width),
+            right: self.right.resolve(ctx, parent_width),
+            bottom: self.bottom.resolve(ctx, parent_width),
+            left: self.left.resolve(ctx, parent_width),
+        }
+    }
+
+    /// The shortest shorthand that describes these edges
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        let values = if self.left != self.right {
+            vec![self.top, self.right, self.bottom, self.left]
+        } else if self.top != self.bottom {
+            vec![self.top, self.right, self.bottom]
+        } else if self.top != self.right {
+            vec![self.top, self.right]
+        } else {
+            vec![self.top]
+        };
+
+        values
+            .iter()
+            .map(CODimension::as_text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{capsule::objs::font::CSFont, layout::font::FontFace};
+
+/// Name of the font text is drawn with when no other one is picked
+pub const DEFAULT_FAMILY: &str = "default";
+
+/// Fonts to draw text with, most wanted first. Each is the name of a
+/// `<font>` or [`DEFAULT_FAMILY`]. Without any, the parent's font is used.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct COFontFamily(pub Vec<String>);
+
+impl COFontFamily {
+    /// The first of the fonts that is declared and loaded. `None` is the
+    /// default font, also used when none of them are.
+    #[must_use]
+    pub fn resolve(&self, fonts: &[CSFont]) -> Option<Arc<FontFace>> {
+        for name in &self.0 {
+            if name == DEFAULT_FAMILY {
+                return None;
+            }
+
+            if let Some(face) = fonts
+                .iter()
+                .find(|font| font.name == *name)
+                .and_then(|font| font.face.clone())
+            {
+                return Some(face);
+            }
+        }
+
+        None
+    }
+
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        if self.0.is_empty() {
+            return "inherit".to_owned();
+        }
+
+        self.0.join(", ")
+    }
+}
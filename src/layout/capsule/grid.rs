@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+use crate::layout::capsule::dimension::CODimension;
+
+/// The size of a single grid column or row
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum COGridTrack {
+    /// as large as the largest item in it
+    Auto,
+    /// a share of the space the other tracks leave, like `1fr`
+    Fraction(f32),
+    Fixed(CODimension),
+}
+
+impl COGridTrack {
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        match self {
+            Self::Auto => "auto".to_owned(),
+            Self::Fraction(v) => format!("{v}fr"),
+            Self::Fixed(dimension) => dimension.as_text(),
+        }
+    }
+}
+
+/// A `grid_template_columns` or `grid_template_rows` list, empty means every
+/// track is implicit and `auto` sized
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct COGridTracks(pub Vec<COGridTrack>);
+
+impl COGridTracks {
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        if self.0.is_empty() {
+            return "none".to_owned();
+        }
+
+        self.0
+            .iter()
+            .map(COGridTrack::as_text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Where an item goes along one axis of its grid
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct COGridPlacement {
+    /// 1-based line the item starts at, negative ones count back from the
+    /// end of the explicit grid. `None` to let the grid pick.
+    pub start: Option<i16>,
+    /// line the item ends at, taken instead of `span` when it can only be
+    /// known once the grid is, because it's negative or `start` is
+    pub end: Option<i16>,
+    /// how many tracks the item covers
+    pub span: u16,
+}
+
+impl Default for COGridPlacement {
+    fn default() -> Self {
+        Self {
+            start: None,
+            end: None,
+            span: 1,
+        }
+    }
+}
+
+impl COGridPlacement {
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        match (self.start, self.end, self.span) {
+            (None, _, 1) => "auto".to_owned(),
+            (None, _, span) => format!("span {span}"),
+            (Some(start), Some(end), _) => format!("{start} / {end}"),
+            (Some(start), None, 1) => start.to_string(),
+            (Some(start), None, span) => format!("{start} / span {span}"),
+        }
+    }
+
+    /// The 0-based track the item starts at, when it's placed, and how many
+    /// tracks it covers in a grid with `tracks` explicit ones. Like in css,
+    /// `-1` is the line after the last explicit track and an end before the
+    /// start is swapped with it.
+    #[must_use]
+    pub fn resolve(&self, tracks: usize) -> (Option<usize>, usize) {
+        let index = |line: i16| {
+            let line = isize::from(line);
+            let index = if line > 0 {
+                line - 1
+            } else {
+                tracks.cast_signed() + 1 + line
+            };
+
+            index.max(0).cast_unsigned()
+        };
+
+        match (self.start.map(index), self.end.map(index)) {
+            (Some(start), Some(end)) => (Some(start.min(end)), start.abs_diff(end).max(1)),
+            (start, _) => (start, usize::from(self.span.max(1))),
+        }
+    }
+}
@@ -1,5 +1,20 @@
 pub mod align;
+pub mod align_content;
+pub mod align_self;
+pub mod border_style;
 pub mod color;
+pub mod corners;
 pub mod dimension;
+pub mod display;
+pub mod edges;
 pub mod flexdir;
+pub mod font_family;
+pub mod grid;
 pub mod justify;
+pub mod object_fit;
+pub mod overflow;
+pub mod position;
+pub mod shadow;
+pub mod transform;
+pub mod visibility;
+pub mod wrap;
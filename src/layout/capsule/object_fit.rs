@@ -0,0 +1,61 @@
+use macroquad::math::{Rect, Vec2};
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+
+/// How an image is sized to the content box of its object
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, EnumString, AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum COObjectFit {
+    /// stretched to fill the box, ignoring its aspect ratio
+    #[default]
+    Fill,
+    /// scaled to fit inside the box, leaving gaps on two sides
+    Contain,
+    /// scaled to cover the box, cropping it on two sides
+    Cover,
+    /// kept at its own size, centered and cropped to the box
+    None,
+}
+
+impl COObjectFit {
+    /// Where an image `size` pixels large goes in `content`, as the part of
+    /// the image that is drawn, in its pixels, and the rect it's drawn over.
+    /// `None` when nothing of it is left to draw.
+    #[must_use]
+    pub fn place(self, size: Vec2, content: Rect) -> Option<(Rect, Rect)> {
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return None;
+        }
+
+        let ratio = content.size() / size;
+        let scale = match self {
+            Self::Fill => ratio,
+            Self::Contain => Vec2::splat(ratio.min_element()),
+            Self::Cover => Vec2::splat(ratio.max_element()),
+            Self::None => Vec2::ONE,
+        };
+        if scale.x <= 0.0 || scale.y <= 0.0 {
+            return None;
+        }
+
+        let placed_size = size * scale;
+        let placed = Rect::new(
+            content.x + (content.w - placed_size.x) / 2.0,
+            content.y + (content.h - placed_size.y) / 2.0,
+            placed_size.x,
+            placed_size.y,
+        );
+        let destination = placed.intersect(content)?;
+        let source = Rect::new(
+            (destination.x - placed.x) / scale.x,
+            (destination.y - placed.y) / scale.y,
+            destination.w / scale.x,
+            destination.h / scale.y,
+        );
+
+        Some((source, destination))
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use stretch::style::Overflow;
+use strum::{AsRefStr, EnumString};
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, EnumString, AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum COOverflow {
+    /// children are drawn even where they spill out of the object
+    #[default]
+    Visible,
+    /// children are clipped to the object's padding box, and keep their
+    /// size instead of shrinking to fit into it
+    Hidden,
+    /// like `hidden`, but the mouse wheel scrolls through the children
+    Scroll,
+}
+
+impl COOverflow {
+    #[must_use]
+    pub const fn as_stretch(&self) -> Overflow {
+        match self {
+            Self::Visible => Overflow::Visible,
+            Self::Hidden => Overflow::Hidden,
+            Self::Scroll => Overflow::Scroll,
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use stretch::style::PositionType;
+use strum::{AsRefStr, EnumString};
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, EnumString, AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum COPosition {
+    /// laid out in the flex flow, then offset by its insets
+    #[default]
+    Relative,
+    /// taken out of the flex flow and placed by its insets inside its parent
+    Absolute,
+}
+
+impl COPosition {
+    #[must_use]
+    pub const fn as_stretch(&self) -> PositionType {
+        match self {
+            Self::Relative => PositionType::Relative,
+            Self::Absolute => PositionType::Absolute,
+        }
+    }
+}
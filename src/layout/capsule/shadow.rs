@@ -0,0 +1,72 @@
+use macroquad::math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{
+    capsule::{
+        color::COColor,
+        dimension::{CODimension, DimensionContext},
+    },
+    computed::ComputedShadow,
+};
+
+/// A single shadow of a `box_shadow`
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct COShadow {
+    pub offset_x: CODimension,
+    pub offset_y: CODimension,
+    /// how far the edge of the shadow fades out
+    pub blur: CODimension,
+    /// how much bigger than the object the shadow is
+    pub spread: CODimension,
+    pub color: COColor,
+}
+
+impl COShadow {
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        format!(
+            "{} {} {} {} {}",
+            self.offset_x.as_text(),
+            self.offset_y.as_text(),
+            self.blur.as_text(),
+            self.spread.as_text(),
+            self.color.as_str()
+        )
+    }
+}
+
+/// Shadows drawn below an object, the first one on top. Empty means `none`.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct COBoxShadow(pub Vec<COShadow>);
+
+impl COBoxShadow {
+    /// Percentages are relative to the width of the object
+    #[must_use]
+    pub fn resolve(&self, ctx: &DimensionContext, width: f32) -> Vec<ComputedShadow> {
+        self.0
+            .iter()
+            .map(|shadow| ComputedShadow {
+                offset: Vec2::new(
+                    shadow.offset_x.resolve(ctx, width),
+                    shadow.offset_y.resolve(ctx, width),
+                ),
+                blur: shadow.blur.resolve(ctx, width).max(0.0),
+                spread: shadow.spread.resolve(ctx, width),
+                color: shadow.color,
+            })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        if self.0.is_empty() {
+            return "none".to_owned();
+        }
+
+        self.0
+            .iter()
+            .map(COShadow::as_text)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
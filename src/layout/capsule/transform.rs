@@ -0,0 +1,97 @@
+use macroquad::math::{Affine2, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::layout::capsule::dimension::{CODimension, DimensionContext};
+
+/// A single function of a `transform`
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum COTransformFunction {
+    /// percentages are relative to the object's own size
+    Translate(CODimension, CODimension),
+    /// clockwise, in degrees
+    Rotate(f32),
+    Scale(f32, f32),
+}
+
+impl COTransformFunction {
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        match self {
+            Self::Translate(x, y) => format!("translate({}, {})", x.as_text(), y.as_text()),
+            Self::Rotate(degrees) => format!("rotate({degrees}deg)"),
+            Self::Scale(x, y) => format!("scale({x}, {y})"),
+        }
+    }
+
+    fn resolve(&self, ctx: &DimensionContext, size: Vec2) -> Affine2 {
+        match self {
+            Self::Translate(x, y) => {
+                Affine2::from_translation(Vec2::new(x.resolve(ctx, size.x), y.resolve(ctx, size.y)))
+            }
+            Self::Rotate(degrees) => Affine2::from_angle(degrees.to_radians()),
+            Self::Scale(x, y) => Affine2::from_scale(Vec2::new(*x, *y)),
+        }
+    }
+}
+
+/// Functions applied to an object and its children when they're drawn,
+/// like css transforms they don't change the layout. Empty means `none`.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct COTransform(pub Vec<COTransformFunction>);
+
+impl COTransform {
+    /// The transform relative to the object's origin, `size` is the size of
+    /// its border box
+    #[must_use]
+    pub fn resolve(&self, ctx: &DimensionContext, size: Vec2) -> Affine2 {
+        // like css, the first function is the outermost one
+        self.0
+            .iter()
+            .fold(Affine2::IDENTITY, |transform, function| {
+                transform * function.resolve(ctx, size)
+            })
+    }
+
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        if self.0.is_empty() {
+            return "none".to_owned();
+        }
+
+        self.0
+            .iter()
+            .map(COTransformFunction::as_text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The point objects are rotated and scaled around, relative to the top
+/// left corner of their border box
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct COTransformOrigin {
+    pub x: CODimension,
+    pub y: CODimension,
+}
+
+impl Default for COTransformOrigin {
+    fn default() -> Self {
+        Self {
+            x: CODimension::Percent(0.5),
+            y: CODimension::Percent(0.5),
+        }
+    }
+}
+
+impl COTransformOrigin {
+    #[must_use]
+    pub fn resolve(&self, ctx: &DimensionContext, size: Vec2) -> Vec2 {
+        Vec2::new(self.x.resolve(ctx, size.x), self.y.resolve(ctx, size.y))
+    }
+
+    #[must_use]
+    pub fn as_text(&self) -> String {
+        format!("{} {}", self.x.as_text(), self.y.as_text())
+    }
+}
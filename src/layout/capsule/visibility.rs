@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, EnumString, AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum COVisibility {
+    #[default]
+    Visible,
+    /// the object and its children keep their space, but aren't drawn and
+    /// can't be clicked
+    Hidden,
+}
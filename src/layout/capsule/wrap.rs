@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use stretch::style::FlexWrap;
+use strum::{AsRefStr, EnumString};
+
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Default, EnumString, AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum COFlexWrap {
+    #[default]
+    NoWrap,
+    Wrap,
+    WrapReverse,
+}
+
+impl COFlexWrap {
+    #[must_use]
+    pub const fn as_stretch(&self) -> FlexWrap {
+        match self {
+            Self::NoWrap => FlexWrap::NoWrap,
+            Self::Wrap => FlexWrap::Wrap,
+            Self::WrapReverse => FlexWrap::WrapReverse,
+        }
+    }
+}
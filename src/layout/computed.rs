@@ -1,7 +1,170 @@
-#[derive(Debug, Default, Clone)]
+use std::sync::Arc;
+
+use macroquad::math::{Affine2, Rect, Vec2};
+
+use crate::{
+    capsule::objs::font::CSFont,
+    layout::{
+        capsule::{
+            color::{COColor, WHITE},
+            visibility::COVisibility,
+        },
+        font::FontFace,
+        styling::Styling,
+    },
+    renderer::constants::DEFAULT_TEXT_SIZE,
+};
+
+/// Resolved size of each side of a margin, padding or border
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ComputedEdges {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl ComputedEdges {
+    #[must_use]
+    pub fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+
+    #[must_use]
+    pub fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+}
+
+/// Resolved radius of each corner of a box
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ComputedCorners {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComputedShadow {
+    pub offset: Vec2,
+    pub blur: f32,
+    pub spread: f32,
+    pub color: COColor,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ComputedStyling {
+    /// the border box, relative to the window
     pub x: f32,
     pub y: f32,
     pub width: f32,
     pub height: f32,
+    pub padding: ComputedEdges,
+    pub border: ComputedEdges,
+    pub border_radius: ComputedCorners,
+    pub box_shadow: Vec<ComputedShadow>,
+    /// maps the object's box onto the window, made of its own `transform`
+    /// and the ones of its ancestors
+    pub transform: Affine2,
+    /// the part of the window the object is visible in, `None` when no
+    /// ancestor clips it
+    pub clip: Option<Rect>,
+    /// how far the object's children can be scrolled
+    pub max_scroll: Vec2,
+    /// false when the object or one of its ancestors is `hidden`, it's
+    /// then neither drawn nor hit
+    pub visible: bool,
+
+    // inherited properties, taken from the closest ancestor that sets them
+    // when the object doesn't set them itself
+    pub color: COColor,
+    pub font_size: u16,
+    /// resolved from `font_family`, `None` is the default font
+    pub font: Option<Arc<FontFace>>,
+}
+
+impl ComputedStyling {
+    /// The box inside the object's border, children are clipped to it
+    #[must_use]
+    pub fn padding_box(&self) -> Rect {
+        Rect::new(
+            self.x + self.border.left,
+            self.y + self.border.top,
+            (self.width - self.border.horizontal()).max(0.0),
+            (self.height - self.border.vertical()).max(0.0),
+        )
+    }
+
+    /// Whether `point`, relative to the window, is inside the object and
+    /// not clipped away
+    #[must_use]
+    pub fn hit_test(&self, point: Vec2) -> bool {
+        let local = self.transform.inverse().transform_point2(point);
+
+        Rect::new(self.x, self.y, self.width, self.height).contains(local)
+            && self.clip.is_none_or(|clip| clip.contains(point))
+    }
+
+    /// The smallest rect of the window that holds `rect` once transformed
+    #[must_use]
+    pub fn window_rect(&self, rect: Rect) -> Rect {
+        let corners = [
+            rect.point(),
+            Vec2::new(rect.right(), rect.y),
+            Vec2::new(rect.x, rect.bottom()),
+            Vec2::new(rect.right(), rect.bottom()),
+        ]
+        .map(|corner| self.transform.transform_point2(corner));
+        let min = corners.into_iter().reduce(Vec2::min).unwrap();
+        let max = corners.into_iter().reduce(Vec2::max).unwrap();
+
+        Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+    }
+
+    /// The box the object's content is laid out in, inside its padding and border
+    #[must_use]
+    pub fn content_box(&self) -> Rect {
+        Rect::new(
+            self.x + self.border.left + self.padding.left,
+            self.y + self.border.top + self.padding.top,
+            (self.width - self.border.horizontal() - self.padding.horizontal()).max(0.0),
+            (self.height - self.border.vertical() - self.padding.vertical()).max(0.0),
+        )
+    }
+
+    /// Resolves the inherited properties of `style` against its parent's
+    /// computed styling, picking its font out of `fonts`
+    pub fn inherit(&mut self, style: &Styling, parent: &Self, fonts: &[CSFont]) {
+        self.color = style.color.unwrap_or(parent.color);
+        self.font_size = style.font_size.unwrap_or(parent.font_size);
+        self.font = if style.font_family.0.is_empty() {
+            parent.font.clone()
+        } else {
+            style.font_family.resolve(fonts)
+        };
+        self.visible = parent.visible && style.visibility == COVisibility::Visible;
+    }
+}
+
+impl Default for ComputedStyling {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+            padding: ComputedEdges::default(),
+            border: ComputedEdges::default(),
+            border_radius: ComputedCorners::default(),
+            box_shadow: Vec::new(),
+            transform: Affine2::IDENTITY,
+            clip: None,
+            max_scroll: Vec2::ZERO,
+            visible: true,
+            color: WHITE,
+            font_size: DEFAULT_TEXT_SIZE,
+            font: None,
+        }
+    }
 }
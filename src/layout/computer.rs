@@ -1,136 +1,1503 @@
 use std::sync::Arc;
 
 use crate::{
-    WINDOW_HEIGHT, WINDOW_WIDTH,
     capsule::{
         Capsule,
         obj::{BoxedCapsuleObject, CapsuleObject},
-        objs::text::CSText,
+        objs::{font::CSFont, image::CSImage, text::CSText},
+    },
+    layout::{
+        capsule::{
+            dimension::{CODimension, DimensionContext},
+            display::CODisplay,
+            overflow::COOverflow,
+            position::COPosition,
+        },
+        computed::{ComputedEdges, ComputedStyling},
+        dirty::{LayoutChanges, LayoutHook},
+        grid::{GridArea, place_items, size_tracks, span_size, track_offsets},
+        stacking::StackingOrder,
+        styling::Styling,
+        text::{TextLine, TextMeasurer, TextStyle, lay_out_text, line_height},
+    },
+    renderer::{
+        display_list::{DisplayList, Recorder},
+        full::render_object,
     },
-    layout::{capsule::dimension::CODimension, computed::ComputedStyling},
 };
-use macroquad::text::measure_text;
-use orx_concurrent_vec::ConcurrentVec;
+use macroquad::math::{Affine2, Rect, Vec2};
 use stretch::{
     Stretch,
     geometry::Size,
-    style::{Dimension, Style},
+    node::{MeasureFunc, Node},
+    number::{Number, OrElse},
+    style::{Dimension, PositionType, Style},
 };
 
-#[allow(clippy::too_many_lines)]
-pub fn compute_layout(capsule: &mut Capsule) {
-    fn styling_to_stretch(
-        child: &orx_concurrent_vec::ConcurrentElement<
-            Arc<dyn CapsuleObject + Send + Sync + 'static>,
-        >,
-    ) -> Style {
-        let binding = child.map(|c| c.base().style.clone());
-        let s = binding.read();
-        let text = child.map(|c| {
-            c.as_any()
-                .downcast_ref::<CSText>()
-                .map(|t| t.text.read().clone())
+/// Grids need a pass to size their tracks once their own size is known, and
+/// another one for whatever depends on the size of their cells
+const DEFERRED_PASSES: usize = 3;
+
+fn styling_to_stretch(object: &dyn CapsuleObject, ctx: &DimensionContext) -> Style {
+    let binding = object.base().style.clone();
+    let s = binding.read();
+
+    Style {
+        size: Size {
+            width: s.width.unwrap_or(CODimension::Auto).as_stretch(ctx),
+            height: s.height.unwrap_or(CODimension::Auto).as_stretch(ctx),
+        },
+        min_size: Size {
+            width: s.min_width.unwrap_or(CODimension::Auto).as_stretch(ctx),
+            height: s.min_height.unwrap_or(CODimension::Auto).as_stretch(ctx),
+        },
+        max_size: Size {
+            width: s.max_width.unwrap_or(CODimension::Auto).as_stretch(ctx),
+            height: s.max_height.unwrap_or(CODimension::Auto).as_stretch(ctx),
+        },
+        aspect_ratio: s.aspect_ratio.map_or(Number::Undefined, Number::Defined),
+        margin: s.margin.as_stretch(ctx),
+        padding: s.padding.as_stretch(ctx),
+        border: s.border_width.as_stretch(ctx),
+        position_type: s.position.as_stretch(),
+        position: stretch::geometry::Rect {
+            start: s.left.unwrap_or(CODimension::Undefined).as_stretch(ctx),
+            end: s.right.unwrap_or(CODimension::Undefined).as_stretch(ctx),
+            top: s.top.unwrap_or(CODimension::Undefined).as_stretch(ctx),
+            bottom: s.bottom.unwrap_or(CODimension::Undefined).as_stretch(ctx),
+        },
+        align_items: s.align.as_stretch(),
+        justify_content: s.justify.as_stretch(),
+        flex_direction: s.flexdir.as_stretch(),
+        flex_wrap: s.flex_wrap.as_stretch(),
+        align_self: s.align_self.as_stretch(),
+        align_content: s.align_content.as_stretch(),
+        flex_grow: s.flex_grow,
+        flex_shrink: s.flex_shrink.unwrap_or(1.0),
+        flex_basis: s.flex_basis.unwrap_or(CODimension::Auto).as_stretch(ctx),
+        overflow: s.overflow.as_stretch(),
+        ..Default::default()
+    }
+}
+
+/// Resolves the padding and border of `object`, percentages are relative
+/// to the width of the parent's content box
+fn resolve_box(
+    object: &dyn CapsuleObject,
+    ctx: &DimensionContext,
+    parent_width: f32,
+) -> (ComputedEdges, ComputedEdges) {
+    let binding = object.base();
+    let style = binding.style.read();
+    let font_size = binding.computed_style.read().font_size;
+    let ctx = ctx.with_font_size(font_size.into());
+
+    (
+        style.padding.resolve(&ctx, parent_width),
+        style.border_width.resolve(&ctx, parent_width),
+    )
+}
+
+fn is_auto(dimension: Option<CODimension>) -> bool {
+    dimension.is_none_or(|d| matches!(d, CODimension::Auto | CODimension::Undefined))
+}
+
+fn inherit(
+    object: &dyn CapsuleObject,
+    parent: &ComputedStyling,
+    fonts: &[CSFont],
+) -> ComputedStyling {
+    let binding = object.base();
+    let style = binding.style.read();
+    let mut computed = binding.computed_style.write();
+    computed.inherit(&style, parent, fonts);
+    computed.clone()
+}
+
+/// Padding and border of `style`, which measured content has to make room
+/// for: stretch sizes are border boxes. Percentages can't be known when
+/// measuring and are ignored.
+fn padding_and_border(style: &Styling, ctx: &DimensionContext) -> Size<f32> {
+    let padding = style.padding.resolve(ctx, 0.0);
+    let border = style.border_width.resolve(ctx, 0.0);
+
+    Size {
+        width: padding.horizontal() + border.horizontal(),
+        height: padding.vertical() + border.vertical(),
+    }
+}
+
+/// Everything needed to measure the text of a [`CSText`]
+#[derive(Clone)]
+struct TextBox {
+    measurer: Arc<dyn TextMeasurer>,
+    runs: Vec<(String, TextStyle)>,
+    font_size: f32,
+    /// padding and border around the text
+    extra: Size<f32>,
+}
+
+impl TextBox {
+    /// Returns `None` for objects that aren't text. `ctx` has to have the
+    /// object's font size, and its runs pick their fonts out of `fonts`.
+    fn of(
+        object: &dyn CapsuleObject,
+        ctx: &DimensionContext,
+        measurer: &Arc<dyn TextMeasurer>,
+        fonts: &[CSFont],
+    ) -> Option<Self> {
+        let text = object.as_any().downcast_ref::<CSText>()?;
+        let base = object.base();
+        let computed = base.computed_style.read();
+        let runs = text
+            .runs
+            .read()
+            .iter()
+            .map(|run| (run.text.clone(), run.style.resolve(&computed, fonts)))
+            .collect();
+
+        Some(Self {
+            measurer: measurer.clone(),
+            runs,
+            font_size: ctx.font_size,
+            extra: padding_and_border(&base.style.read(), ctx),
+        })
+    }
+
+    /// The text wrapped to `max_width`
+    fn lines(&self, max_width: Option<f32>) -> Vec<TextLine> {
+        lay_out_text(
+            &self.runs,
+            max_width,
+            line_height(self.font_size),
+            self.measurer.as_ref(),
+        )
+    }
+
+    /// Wraps the text to the width stretch offers, or to `wrap_width` when
+    /// stretch doesn't know the width yet
+    fn measure(&self, size: Size<Number>, wrap_width: Option<f32>) -> Size<f32> {
+        let max_width = match size.width {
+            Number::Defined(width) => Some(width - self.extra.width),
+            Number::Undefined => wrap_width,
+        };
+        let lines = self.lines(max_width);
+        let widest = lines.iter().map(|line| line.width).fold(0.0, f32::max);
+        let height = lines.iter().map(|line| line.height).sum::<f32>();
+
+        Size {
+            width: size.width.or_else(widest + self.extra.width),
+            height: size.height.or_else(height + self.extra.height),
+        }
+    }
+
+    fn into_measure(self, wrap_width: Option<f32>) -> MeasureFunc {
+        Box::new(move |size| Ok(self.measure(size, wrap_width)))
+    }
+}
+
+/// Everything needed to measure a [`CSImage`]
+#[derive(Clone, Copy)]
+struct ImageBox {
+    /// size of the image, or of its alt text when it isn't loaded
+    natural: Vec2,
+    /// whether the other side follows when only one is known, only images
+    /// keep their aspect ratio
+    keep_ratio: bool,
+    /// padding and border around the image
+    extra: Size<f32>,
+}
+
+impl ImageBox {
+    /// Returns `None` for objects that aren't images. `ctx` has to have the
+    /// object's font size.
+    fn of(
+        object: &dyn CapsuleObject,
+        ctx: &DimensionContext,
+        measurer: &Arc<dyn TextMeasurer>,
+    ) -> Option<Self> {
+        let image = object.as_any().downcast_ref::<CSImage>()?;
+        let natural = image.natural_size();
+        let base = object.base();
+
+        Some(Self {
+            natural: natural.unwrap_or_else(|| {
+                Vec2::new(
+                    measurer.line_width(
+                        &image.alt.read(),
+                        base.computed_style.read().font.as_ref(),
+                        ctx.font_size,
+                    ),
+                    line_height(ctx.font_size),
+                )
+            }),
+            keep_ratio: natural.is_some_and(|natural| natural.x > 0.0 && natural.y > 0.0),
+            extra: padding_and_border(&base.style.read(), ctx),
+        })
+    }
+
+    /// The natural size, or the size stretch offers with the missing side
+    /// scaled to keep the aspect ratio
+    fn measure(&self, size: Size<Number>) -> Size<f32> {
+        let content = |side: Number, extra: f32| match side {
+            Number::Defined(side) => Some((side - extra).max(0.0)),
+            Number::Undefined => None,
+        };
+        let natural = match (
+            content(size.width, self.extra.width),
+            content(size.height, self.extra.height),
+        ) {
+            (Some(width), None) if self.keep_ratio => {
+                Vec2::new(width, width * self.natural.y / self.natural.x)
+            }
+            (None, Some(height)) if self.keep_ratio => {
+                Vec2::new(height * self.natural.x / self.natural.y, height)
+            }
+            _ => self.natural,
+        };
+
+        Size {
+            width: size.width.or_else(natural.x + self.extra.width),
+            height: size.height.or_else(natural.y + self.extra.height),
+        }
+    }
+
+    fn into_measure(self) -> MeasureFunc {
+        Box::new(move |size| Ok(self.measure(size)))
+    }
+}
+
+struct LayoutEntry {
+    object: BoxedCapsuleObject,
+    node: Node,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// width of the text when it isn't wrapped at all
+    natural_width: Option<f32>,
+    /// what the text's measure function wraps at when stretch doesn't know
+    wrap_width: Option<f32>,
+    /// content width the text's lines were last wrapped to
+    wrapped_at: Option<f32>,
+    /// whether the object looks different since it was last recorded into
+    /// the display list
+    repaint: bool,
+    /// whether the object or one of its descendants was restyled, or had
+    /// something resolved for it, since it was last laid out
+    relayout: bool,
+    /// the size of the border box its children were last resolved in
+    resolved_size: Option<Vec2>,
+    /// where stretch put the border box in the parent's when it was last
+    /// applied
+    applied: Option<Rect>,
+    /// what the object's place in the paint order was last worked out from:
+    /// its `z_index`, whether it's laid out and whether it's visible
+    stacking: Option<(Option<i32>, bool, bool)>,
+    /// how many times the object was resolved or applied, for the tests to
+    /// check what was laid out again
+    #[cfg(test)]
+    laid_out: usize,
+}
+
+/// A stretch node tree mirroring the capsule's objects that is kept across
+/// frames. Objects report their style changes through [`LayoutChanges`], so
+/// only the nodes of the objects that changed are restyled and stretch only
+/// has to redo the parts of the layout they affect.
+///
+/// What the objects draw is recorded into a [`DisplayList`], again only for
+/// the objects that changed, so nothing has to walk the capsule to draw it.
+pub struct LayoutTree {
+    stretch: Stretch,
+    /// indexed by the objects' layout hooks, the root view comes first
+    entries: Vec<LayoutEntry>,
+    changes: LayoutChanges,
+    ctx: DimensionContext,
+    measurer: Arc<dyn TextMeasurer>,
+    display_list: DisplayList,
+    /// whether the paint order may have changed since the display list was
+    /// last put in order
+    restack: bool,
+    /// what `font_family` picks from, the capsule's `<font>`s
+    fonts: Vec<CSFont>,
+}
+
+impl LayoutTree {
+    /// Builds the tree for `capsule` and lays it out in its viewport, with
+    /// text measured by `measurer`
+    #[must_use]
+    pub fn new(capsule: &Capsule, measurer: Arc<dyn TextMeasurer>) -> Self {
+        let mut tree = Self {
+            stretch: Stretch::new(),
+            entries: Vec::new(),
+            changes: LayoutChanges::default(),
+            ctx: DimensionContext {
+                font_size: 0.0,
+                root_font_size: 0.0,
+                viewport_width: capsule.viewport.x,
+                viewport_height: capsule.viewport.y,
+            },
+            measurer,
+            display_list: DisplayList::default(),
+            restack: true,
+            fonts: capsule.meta.fonts.clone(),
+        };
+
+        tree.build(Arc::new(capsule.view.clone()), None);
+        tree.restyle(0);
+        tree.compute();
+        tree.update_display_list();
+        tree.damage_viewport();
+        tree
+    }
+
+    #[must_use]
+    pub const fn display_list(&self) -> &DisplayList {
+        &self.display_list
+    }
+
+    /// For repainting, which takes the damage out of the display list
+    pub const fn display_list_mut(&mut self) -> &mut DisplayList {
+        &mut self.display_list
+    }
+
+    fn damage_viewport(&mut self) {
+        self.display_list.damage(Rect::new(
+            0.0,
+            0.0,
+            self.ctx.viewport_width,
+            self.ctx.viewport_height,
+        ));
+    }
+
+    fn build(&mut self, object: BoxedCapsuleObject, parent: Option<usize>) -> usize {
+        let index = self.entries.len();
+        let node = self.stretch.new_node(Style::default(), vec![]).unwrap();
+
+        {
+            let base = object.base();
+            let mut style = base.style.write();
+            style.set_layout_hook(self.changes.hook(index));
+            style.set_non_dirty();
+        }
+
+        if let Some(parent) = parent {
+            self.stretch
+                .add_child(self.entries[parent].node, node)
+                .unwrap();
+        }
+
+        self.entries.push(LayoutEntry {
+            object: object.clone(),
+            node,
+            parent,
+            children: Vec::new(),
+            natural_width: None,
+            wrap_width: None,
+            wrapped_at: None,
+            repaint: true,
+            relayout: true,
+            resolved_size: None,
+            applied: None,
+            stacking: None,
+            #[cfg(test)]
+            laid_out: 0,
         });
-        let mut width = s.width.unwrap_or(CODimension::Auto).as_stretch();
-        let height = text.map_or_else(
-            || s.height.unwrap_or(CODimension::Auto).as_stretch(),
-            |t| {
-                let measured = measure_text(&t, None, s.font_size, 1.0);
-                width = Dimension::Points(measured.width);
-                Dimension::Points(measured.height)
+
+        for child in object.base().children_vec() {
+            let child = self.build(child, Some(index));
+            self.entries[index].children.push(child);
+        }
+
+        index
+    }
+
+    /// Lays the tree out again for a new viewport size
+    pub fn resize(&mut self, viewport: Vec2) {
+        self.ctx.viewport_width = viewport.x;
+        self.ctx.viewport_height = viewport.y;
+
+        // viewport units can be used anywhere, so everything is restyled
+        self.restyle(0);
+        self.compute();
+        self.update_display_list();
+        self.damage_viewport();
+    }
+
+    /// Applies the style changes reported since the last update and lays
+    /// the tree out again if there were any
+    pub fn update(&mut self) {
+        let changes = self.changes.take();
+
+        if !changes.restyled.is_empty() {
+            for index in changes.restyled {
+                self.entries[index].object.set_non_dirty();
+                self.restyle(index);
+            }
+
+            self.compute();
+        }
+
+        // scrolling doesn't change any sizes, only where the children are,
+        // and the layout may have left the scrolled objects alone
+        for index in changes.scrolled {
+            self.apply_children(index, true);
+        }
+
+        self.update_display_list();
+    }
+
+    /// Records the objects that changed into the display list again, and
+    /// puts it back in paint order when that may have changed
+    fn update_display_list(&mut self) {
+        if std::mem::take(&mut self.restack) {
+            let order = StackingOrder::of(self.entries[0].object.clone())
+                .paint_order()
+                .filter_map(|o| o.base().style.read().layout_hook().map(LayoutHook::index))
+                .collect();
+            self.display_list.set_order(order);
+        }
+
+        let mut painted = vec![false; self.entries.len()];
+        for index in self.display_list.order() {
+            painted[*index] = true;
+        }
+
+        for (index, painted) in painted.into_iter().enumerate() {
+            if !std::mem::take(&mut self.entries[index].repaint) {
+                continue;
+            }
+
+            // objects that aren't painted anymore lose their item
+            let item = painted.then(|| {
+                let mut recorder = Recorder::new(self.measurer.clone());
+                render_object(self.entries[index].object.as_ref(), &mut recorder);
+                recorder.finish()
+            });
+            self.display_list.set_item(index, item);
+        }
+    }
+
+    /// Updates the nodes of the object at `index` and its descendants, which
+    /// inherit from it, to match their styles
+    fn restyle(&mut self, index: usize) {
+        let parent = self.entries[index]
+            .parent
+            .map_or_else(ComputedStyling::default, |p| {
+                self.entries[p].object.base().computed_style.read().clone()
+            });
+
+        self.restyle_subtree(index, &parent);
+
+        // the object may have been hidden or shown with `display`
+        if let Some(parent) = self.entries[index].parent {
+            self.attach_children(parent);
+        }
+
+        // the layout of the restyled objects is only reached through their
+        // ancestors
+        let mut ancestor = self.entries[index].parent;
+        while let Some(index) = ancestor {
+            self.entries[index].relayout = true;
+            ancestor = self.entries[index].parent;
+        }
+    }
+
+    /// Whether the object at `index` has to be laid out again even though
+    /// the size of its parent stayed the same
+    fn needs_layout(&self, index: usize) -> bool {
+        let entry = &self.entries[index];
+        let size = self.stretch.layout(entry.node).unwrap().size;
+
+        entry.relayout || entry.resolved_size != Some(Vec2::new(size.width, size.height))
+    }
+
+    /// The children of the object at `index` that take part in the layout,
+    /// the ones with `display="none"` are left out
+    fn laid_out_children(&self, index: usize) -> Vec<usize> {
+        self.entries[index]
+            .children
+            .iter()
+            .filter(|child| {
+                self.entries[**child].object.base().style.read().display != CODisplay::None
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Gives the node of the object at `index` the nodes of its laid out
+    /// children, so stretch doesn't make room for the others
+    fn attach_children(&mut self, index: usize) {
+        let children = self
+            .laid_out_children(index)
+            .into_iter()
+            .map(|child| self.entries[child].node)
+            .collect();
+
+        self.stretch
+            .set_children(self.entries[index].node, children)
+            .unwrap();
+    }
+
+    fn restyle_subtree(&mut self, index: usize, parent: &ComputedStyling) {
+        let object = self.entries[index].object.clone();
+        let node = self.entries[index].node;
+        let computed = inherit(object.as_ref(), parent, &self.fonts);
+
+        if index == 0 {
+            self.ctx.root_font_size = computed.font_size.into();
+        }
+
+        let ctx = self.ctx.with_font_size(computed.font_size.into());
+        let mut style = styling_to_stretch(object.as_ref(), &ctx);
+
+        if index == 0 {
+            style.size = Size {
+                width: Dimension::Points(ctx.viewport_width),
+                height: Dimension::Points(ctx.viewport_height),
+            };
+        }
+
+        if let Some(parent) = self.entries[index].parent {
+            let parent = self.entries[parent].object.base();
+            let parent_style = parent.style.read();
+
+            // stretch has no automatic minimum size for flex items, so content
+            // would get squashed into its container instead of being scrolled
+            // to, unless it was asked to shrink
+            if parent_style.overflow != COOverflow::Visible
+                && object.base().style.read().flex_shrink.is_none()
+            {
+                style.flex_shrink = 0.0;
+            }
+
+            // stretch knows nothing about grids, their items are taken out of
+            // the flow and placed by `layout_grid`
+            if parent_style.display == CODisplay::Grid {
+                style.position_type = PositionType::Absolute;
+            }
+        }
+
+        self.stretch.set_style(node, style).unwrap();
+
+        if let Some(text) = TextBox::of(object.as_ref(), &ctx, &self.measurer, &self.fonts) {
+            self.stretch
+                .set_measure(node, Some(text.into_measure(None)))
+                .unwrap();
+        } else if let Some(image) = ImageBox::of(object.as_ref(), &ctx, &self.measurer) {
+            self.stretch
+                .set_measure(node, Some(image.into_measure()))
+                .unwrap();
+        }
+
+        self.attach_children(index);
+
+        // `z_index`, `display` and `visibility` decide the paint order
+        let stacking = {
+            let base = object.base();
+            let style = base.style.read();
+            Some((
+                style.z_index,
+                style.display != CODisplay::None,
+                computed.visible,
+            ))
+        };
+        let entry = &mut self.entries[index];
+
+        if entry.stacking != stacking {
+            entry.stacking = stacking;
+            self.restack = true;
+        }
+
+        entry.natural_width = None;
+        entry.wrap_width = None;
+        entry.wrapped_at = None;
+        entry.repaint = true;
+        entry.relayout = true;
+
+        for child in entry.children.clone() {
+            self.restyle_subtree(child, &computed);
+        }
+    }
+
+    fn compute(&mut self) {
+        let root = self.entries[0].node;
+
+        self.stretch
+            .compute_layout(root, Size::undefined())
+            .unwrap();
+
+        for _ in 0..DEFERRED_PASSES {
+            if !self.resolve_children(0, self.ctx.viewport_width) {
+                break;
+            }
+
+            self.stretch
+                .compute_layout(root, Size::undefined())
+                .unwrap();
+        }
+
+        self.apply_layout(
+            0,
+            Vec2::ZERO,
+            self.ctx.viewport_width,
+            Affine2::IDENTITY,
+            None,
+        );
+    }
+
+    /// Some things can only be resolved once the parents have been laid out
+    /// once: stretch can't express a `calc()` mixing percentages with other
+    /// units, and doesn't tell measure functions how much room there is when
+    /// the width is `auto`, so text has to be told what width to wrap at.
+    /// Grids are laid out here too. Returns whether any node had to be changed.
+    fn resolve_deferred(&mut self, index: usize, parent_size: Size<f32>) -> bool {
+        #[cfg(test)]
+        {
+            self.entries[index].laid_out += 1;
+        }
+
+        let object = self.entries[index].object.clone();
+        let node = self.entries[index].node;
+        let s = object.base().style.read().clone();
+
+        let font_size = object.base().computed_style.read().font_size;
+        let node_ctx = self.ctx.with_font_size(font_size.into());
+        let mut changed = false;
+
+        if let Some(text) = TextBox::of(object.as_ref(), &node_ctx, &self.measurer, &self.fonts) {
+            let margin = s.margin.resolve(&node_ctx, parent_size.width);
+            let wrap_width = parent_size.width - margin.horizontal();
+            let entry = &mut self.entries[index];
+            let natural_width = *entry
+                .natural_width
+                .get_or_insert_with(|| text.measure(Size::undefined(), None).width);
+            let wrap_width = (natural_width > wrap_width).then_some(wrap_width);
+
+            if entry.wrap_width != wrap_width {
+                entry.wrap_width = wrap_width;
+                self.stretch
+                    .set_measure(node, Some(text.into_measure(wrap_width)))
+                    .unwrap();
+                changed = true;
+            }
+        } else {
+            let mut style = *self.stretch.style(node).unwrap();
+            let mut fix = |dimension: Option<CODimension>, target: &mut Dimension, parent: f32| {
+                if let Some(dimension) = dimension.filter(|d| d.is_mixed_calc(&node_ctx)) {
+                    let resolved = Dimension::Points(dimension.resolve(&node_ctx, parent));
+
+                    if *target != resolved {
+                        *target = resolved;
+                        changed = true;
+                    }
+                }
+            };
+
+            fix(s.width, &mut style.size.width, parent_size.width);
+            fix(s.height, &mut style.size.height, parent_size.height);
+            fix(s.min_width, &mut style.min_size.width, parent_size.width);
+            fix(s.min_height, &mut style.min_size.height, parent_size.height);
+            fix(s.max_width, &mut style.max_size.width, parent_size.width);
+            fix(s.max_height, &mut style.max_size.height, parent_size.height);
+
+            if changed {
+                self.stretch.set_style(node, style).unwrap();
+            }
+        }
+
+        self.resolve_children(index, parent_size.width) | changed
+    }
+
+    /// Resolves what was deferred for the children of the object at `index`,
+    /// laying them out first if it's a grid. When the object kept its size,
+    /// only the children that need it are resolved again.
+    fn resolve_children(&mut self, index: usize, parent_width: f32) -> bool {
+        let object = self.entries[index].object.clone();
+        let s = object.base().style.read().clone();
+        let size = self.stretch.layout(self.entries[index].node).unwrap().size;
+        let (padding, border) = resolve_box(object.as_ref(), &self.ctx, parent_width);
+        let content_size = Size {
+            width: size.width - padding.horizontal() - border.horizontal(),
+            height: size.height - padding.vertical() - border.vertical(),
+        };
+        let resized = self.entries[index]
+            .resolved_size
+            .replace(Vec2::new(size.width, size.height))
+            != Some(Vec2::new(size.width, size.height));
+        let mut changed = false;
+
+        if s.display == CODisplay::Grid && (resized || self.entries[index].relayout) {
+            changed |= self.layout_grid(index, &s, content_size, padding, border);
+        }
+
+        for child in self.laid_out_children(index) {
+            if resized || self.needs_layout(child) {
+                changed |= self.resolve_deferred(child, content_size);
+            }
+        }
+
+        // what changed has to be reached again in the next pass
+        if changed {
+            self.entries[index].relayout = true;
+        }
+
+        changed
+    }
+
+    /// Places the children of the grid at `index` into its cells, stretching
+    /// the ones with an `auto` size to fill them and offsetting them by their
+    /// insets, and grows the grid to fit its rows when its height is `auto`.
+    /// Absolutely positioned children are left to stretch, like in a flex
+    /// container. Returns whether any node had to be changed.
+    fn layout_grid(
+        &mut self,
+        index: usize,
+        s: &Styling,
+        content_size: Size<f32>,
+        padding: ComputedEdges,
+        border: ComputedEdges,
+    ) -> bool {
+        let children: Vec<_> = self
+            .laid_out_children(index)
+            .into_iter()
+            .filter(|child| {
+                self.entries[*child].object.base().style.read().position != COPosition::Absolute
+            })
+            .collect();
+        let font_size = self.entries[index]
+            .object
+            .base()
+            .computed_style
+            .read()
+            .font_size;
+        let ctx = self.ctx.with_font_size(font_size.into());
+
+        let placements: Vec<_> = children
+            .iter()
+            .map(|child| {
+                let style = self.entries[*child].object.base().style.read().clone();
+                (style.grid_column, style.grid_row)
+            })
+            .collect();
+        let areas = place_items(
+            s.grid_template_columns.0.len(),
+            s.grid_template_rows.0.len(),
+            &placements,
+        );
+        let column_count = areas
+            .iter()
+            .map(GridArea::column_end)
+            .max()
+            .unwrap_or(0)
+            .max(s.grid_template_columns.0.len());
+        let row_count = areas
+            .iter()
+            .map(GridArea::row_end)
+            .max()
+            .unwrap_or(0)
+            .max(s.grid_template_rows.0.len());
+        let margins: Vec<_> = children
+            .iter()
+            .map(|child| {
+                let base = self.entries[*child].object.base();
+                let font_size = base.computed_style.read().font_size;
+                let margin = base.style.read().margin;
+                margin.resolve(
+                    &self.ctx.with_font_size(font_size.into()),
+                    content_size.width,
+                )
+            })
+            .collect();
+        // like `position: relative` in a flex container, insets move the item
+        // from where it would be without taking up any more room
+        let insets: Vec<_> = children
+            .iter()
+            .map(|child| {
+                let base = self.entries[*child].object.base();
+                let font_size = base.computed_style.read().font_size;
+                let ctx = self.ctx.with_font_size(font_size.into());
+                let style = base.style.read();
+                let inset = |start: Option<CODimension>, end: Option<CODimension>, parent| {
+                    start
+                        .map(|start| start.resolve(&ctx, parent))
+                        .or_else(|| end.map(|end| -end.resolve(&ctx, parent)))
+                        .unwrap_or(0.0)
+                };
+
+                Vec2::new(
+                    inset(style.left, style.right, content_size.width),
+                    inset(style.top, style.bottom, content_size.height),
+                )
+            })
+            .collect();
+
+        // the height only means something to the rows when it doesn't
+        // depend on them
+        let definite_height = (index == 0 || !is_auto(s.height)).then_some(content_size.height);
+        let column_gap = s
+            .column_gap
+            .map_or(0.0, |gap| gap.resolve(&ctx, content_size.width));
+        let row_gap = s
+            .row_gap
+            .map_or(0.0, |gap| gap.resolve(&ctx, definite_height.unwrap_or(0.0)));
+
+        let items = || areas.iter().zip(&children).zip(&margins);
+        let columns = size_tracks(
+            &s.grid_template_columns.0,
+            column_count,
+            Some(content_size.width),
+            column_gap,
+            &ctx,
+            |track| {
+                items()
+                    .filter(|((area, _), _)| area.column == track && area.column_span == 1)
+                    .map(|((_, child), margin)| {
+                        self.natural_size(*child, None).width + margin.horizontal()
+                    })
+                    .fold(0.0, f32::max)
+            },
+        );
+        let widths: Vec<_> = items()
+            .map(|((area, _), margin)| {
+                (span_size(&columns, area.column, area.column_span, column_gap)
+                    - margin.horizontal())
+                .max(0.0)
+            })
+            .collect();
+        let rows = size_tracks(
+            &s.grid_template_rows.0,
+            row_count,
+            definite_height,
+            row_gap,
+            &ctx,
+            |track| {
+                items()
+                    .zip(&widths)
+                    .filter(|(((area, _), _), _)| area.row == track && area.row_span == 1)
+                    .map(|(((_, child), margin), width)| {
+                        self.natural_size(*child, Some(*width)).height + margin.vertical()
+                    })
+                    .fold(0.0, f32::max)
             },
         );
 
-        Style {
-            size: stretch::geometry::Size { width, height },
-            align_items: s.align.as_stretch(),
-            justify_content: s.justify.as_stretch(),
-            flex_direction: s.flexdir.as_stretch(),
-            ..Default::default()
+        let column_offsets = track_offsets(&columns, column_gap);
+        let row_offsets = track_offsets(&rows, row_gap);
+        let mut changed = false;
+
+        for ((((area, child), margin), width), inset) in items().zip(&widths).zip(&insets) {
+            let node = self.entries[*child].node;
+            let mut style = *self.stretch.style(node).unwrap();
+            let (own_width, own_height) = {
+                let base = self.entries[*child].object.base();
+                let style = base.style.read();
+                (style.width, style.height)
+            };
+            let position = stretch::geometry::Rect {
+                start: Dimension::Points(padding.left + column_offsets[area.column] + inset.x),
+                end: Dimension::Undefined,
+                top: Dimension::Points(padding.top + row_offsets[area.row] + inset.y),
+                bottom: Dimension::Undefined,
+            };
+            let height =
+                (span_size(&rows, area.row, area.row_span, row_gap) - margin.vertical()).max(0.0);
+            let size = Size {
+                width: if is_auto(own_width) {
+                    Dimension::Points(*width)
+                } else {
+                    style.size.width
+                },
+                height: if is_auto(own_height) {
+                    Dimension::Points(height)
+                } else {
+                    style.size.height
+                },
+            };
+
+            if style.position != position || style.size != size {
+                style.position = position;
+                style.size = size;
+                self.stretch.set_style(node, style).unwrap();
+                changed = true;
+            }
         }
+
+        if definite_height.is_none() {
+            let node = self.entries[index].node;
+            let mut style = *self.stretch.style(node).unwrap();
+            let height = Dimension::Points(
+                span_size(&rows, 0, row_count, row_gap) + padding.vertical() + border.vertical(),
+            );
+
+            if style.size.height != height {
+                style.size.height = height;
+                self.stretch.set_style(node, style).unwrap();
+                changed = true;
+            }
+        }
+
+        changed
     }
 
-    fn build_node(
-        stretch: &mut Stretch,
-        child: &orx_concurrent_vec::ConcurrentElement<BoxedCapsuleObject>,
-    ) -> stretch::node::Node {
-        let node_style = styling_to_stretch(child);
+    /// Copies the subtree of the object at `index` into `scratch`, so it can
+    /// be measured without touching the layout of the tree
+    fn copy_subtree(&self, scratch: &mut Stretch, index: usize) -> Node {
+        let entry = &self.entries[index];
+        let style = *self.stretch.style(entry.node).unwrap();
+        let font_size = entry.object.base().computed_style.read().font_size;
+        let ctx = self.ctx.with_font_size(font_size.into());
 
-        let children_nodes: Vec<_> = {
-            let child_children: Arc<orx_concurrent_vec::ConcurrentVec<BoxedCapsuleObject>> =
-                child.map(|c| c.base().children.clone());
-            child_children
-                .iter()
-                .map(|ch| build_node(stretch, ch))
-                .collect()
-        };
+        if let Some(text) = TextBox::of(entry.object.as_ref(), &ctx, &self.measurer, &self.fonts) {
+            return scratch
+                .new_leaf(style, text.into_measure(entry.wrap_width))
+                .unwrap();
+        }
+
+        if let Some(image) = ImageBox::of(entry.object.as_ref(), &ctx, &self.measurer) {
+            return scratch.new_leaf(style, image.into_measure()).unwrap();
+        }
 
-        stretch.new_node(node_style, children_nodes).unwrap()
+        let children = self
+            .laid_out_children(index)
+            .into_iter()
+            .map(|child| self.copy_subtree(scratch, child))
+            .collect();
+        scratch.new_node(style, children).unwrap()
     }
 
+    /// The border box the object at `index` would have on its own, `width`
+    /// wide when given and its width is `auto`. Margins aren't included.
+    fn natural_size(&self, index: usize, width: Option<f32>) -> Size<f32> {
+        let mut scratch = Stretch::new();
+        let node = self.copy_subtree(&mut scratch, index);
+        let object = self.entries[index].object.as_ref();
+        let font_size = object.base().computed_style.read().font_size;
+
+        // the copy has the size of the cell it's in, start over from its own
+        let mut style = styling_to_stretch(object, &self.ctx.with_font_size(font_size.into()));
+        style.position_type = PositionType::Relative;
+        style.position = stretch::geometry::Rect::default();
+        style.margin = stretch::geometry::Rect::default();
+        if let Some(width) = width
+            && matches!(style.size.width, Dimension::Auto | Dimension::Undefined)
+        {
+            style.size.width = Dimension::Points(width);
+        }
+
+        scratch.set_style(node, style).unwrap();
+        scratch
+            .compute_layout(
+                node,
+                Size {
+                    width: width.map_or(Number::Undefined, Number::Defined),
+                    height: Number::Undefined,
+                },
+            )
+            .unwrap();
+        scratch.layout(node).unwrap().size
+    }
+
+    /// Writes the layout of the object at `index` and its descendants into
+    /// their computed styles. `origin` is where the parent's border box
+    /// ends up on the window once scrolled, `transform` how the parent is
+    /// transformed and `clip` what it clips its children to.
     fn apply_layout(
-        stretch: &Stretch,
-        node: stretch::node::Node,
-        child: &orx_concurrent_vec::ConcurrentElement<BoxedCapsuleObject>,
-        parent_abs_x: f32,
-        parent_abs_y: f32,
+        &mut self,
+        index: usize,
+        origin: Vec2,
+        parent_width: f32,
+        transform: Affine2,
+        clip: Option<Rect>,
     ) {
-        let layout = stretch.layout(node).unwrap();
-
-        let abs_x = parent_abs_x + layout.location.x;
-        let abs_y = parent_abs_y + layout.location.y;
-
-        child.map(|c| {
-            let binding = c.base();
-            let mut computed = binding.computed_style.write();
-            *computed = ComputedStyling {
-                x: abs_x,
-                y: abs_y,
-                width: layout.size.width,
-                height: layout.size.height,
-            };
-        });
+        let object = self.entries[index].object.clone();
+        let layout = *self.stretch.layout(self.entries[index].node).unwrap();
+        let entry = &mut self.entries[index];
+        entry.relayout = false;
+        entry.applied = Some(Rect::new(
+            layout.location.x,
+            layout.location.y,
+            layout.size.width,
+            layout.size.height,
+        ));
+
+        #[cfg(test)]
+        {
+            entry.laid_out += 1;
+        }
+
+        // the furthest any child reaches, relative to the border box
+        let extent = self
+            .laid_out_children(index)
+            .into_iter()
+            .map(|child| self.stretch.layout(self.entries[child].node).unwrap())
+            .fold(Vec2::ZERO, |extent, child| {
+                extent.max(Vec2::new(
+                    child.location.x + child.size.width,
+                    child.location.y + child.size.height,
+                ))
+            });
+
+        let (padding, border) = resolve_box(object.as_ref(), &self.ctx, parent_width);
+        let base = object.base();
+        let (border_radius, box_shadow) = {
+            let style = base.style.read();
+            (style.border_radius, style.box_shadow.clone())
+        };
+        let (content_width, moved) = {
+            let mut computed = base.computed_style.write();
+            let before = computed.clone();
+            let ctx = self.ctx.with_font_size(computed.font_size.into());
+            let size = Vec2::new(layout.size.width, layout.size.height);
+            computed.x = origin.x + layout.location.x;
+            computed.y = origin.y + layout.location.y;
+            computed.width = layout.size.width;
+            computed.height = layout.size.height;
+            computed.padding = padding;
+            computed.border = border;
+            computed.border_radius = border_radius.resolve(&ctx, size);
+            computed.box_shadow = box_shadow.resolve(&ctx, size.x);
+            computed.transform = transform * self.own_transform(object.as_ref(), &computed);
+            computed.clip = clip;
+            computed.max_scroll = (extent
+                + Vec2::new(padding.right + border.right, padding.bottom + border.bottom)
+                - Vec2::new(layout.size.width, layout.size.height))
+            .max(Vec2::ZERO);
+
+            let moved = *computed != before;
+            if moved {
+                self.entries[index].repaint = true;
+            }
+
+            (computed.content_box().w, moved)
+        };
+
+        let ctx = self
+            .ctx
+            .with_font_size(base.computed_style.read().font_size.into());
+
+        if self.entries[index].wrapped_at != Some(content_width)
+            && let Some(text) = object.as_any().downcast_ref::<CSText>()
+            && let Some(text_box) = TextBox::of(object.as_ref(), &ctx, &self.measurer, &self.fonts)
+        {
+            *text.lines.write() = text_box.lines(Some(content_width));
+            self.entries[index].wrapped_at = Some(content_width);
+        }
+
+        self.apply_children(index, moved);
+    }
+
+    /// Lays out the children of the object at `index` inside of it,
+    /// scrolling and clipping them unless its overflow is visible. Unless
+    /// the object `moved`, only the children that need it are laid out again.
+    fn apply_children(&mut self, index: usize, moved: bool) {
+        let base = self.entries[index].object.base();
+        let (origin, content_width, transform, clip) = {
+            let style = base.style.read();
+            let computed = base.computed_style.read();
+            let origin = Vec2::new(computed.x, computed.y);
+            let content_width =
+                computed.width - computed.padding.horizontal() - computed.border.horizontal();
+
+            if style.overflow == COOverflow::Visible {
+                (origin, content_width, computed.transform, computed.clip)
+            } else {
+                // the content may have shrunk since the object was scrolled
+                let mut scroll = base.scroll.write();
+                *scroll = scroll.clamp(Vec2::ZERO, computed.max_scroll);
 
-        let child_children: Arc<orx_concurrent_vec::ConcurrentVec<BoxedCapsuleObject>> =
-            child.map(|c| c.base().children.clone());
-        let child_nodes = stretch.children(node).unwrap();
+                // scissor rects can't be rotated, so transformed objects
+                // clip to what their padding box covers on the window
+                let padding_box = computed.window_rect(computed.padding_box());
+                let clip = computed.clip.map_or(padding_box, |clip| {
+                    clip.intersect(padding_box).unwrap_or(Rect::new(
+                        padding_box.x,
+                        padding_box.y,
+                        0.0,
+                        0.0,
+                    ))
+                });
+
+                (
+                    origin - *scroll,
+                    content_width,
+                    computed.transform,
+                    Some(clip),
+                )
+            }
+        };
 
-        for (child_node, ch) in child_nodes.into_iter().zip(child_children.iter()) {
-            apply_layout(stretch, child_node, ch, abs_x, abs_y);
+        for child in self.laid_out_children(index) {
+            let entry = &self.entries[child];
+            let layout = self.stretch.layout(entry.node).unwrap();
+            let bounds = Rect::new(
+                layout.location.x,
+                layout.location.y,
+                layout.size.width,
+                layout.size.height,
+            );
+
+            if moved || entry.relayout || entry.applied != Some(bounds) {
+                self.apply_layout(child, origin, content_width, transform, clip);
+            }
         }
     }
 
-    let mut stretch = Stretch::new();
+    /// The transform of `object` alone, around its transform origin
+    fn own_transform(&self, object: &dyn CapsuleObject, computed: &ComputedStyling) -> Affine2 {
+        let style = object.base().style.read().clone();
+
+        if style.transform.0.is_empty() {
+            return Affine2::IDENTITY;
+        }
+
+        let ctx = self.ctx.with_font_size(computed.font_size.into());
+        let size = Vec2::new(computed.width, computed.height);
+        let origin = Vec2::new(computed.x, computed.y) + style.transform_origin.resolve(&ctx, size);
+
+        Affine2::from_translation(origin)
+            * style.transform.resolve(&ctx, size)
+            * Affine2::from_translation(-origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{capsule::parser::parse_capsule, layout::text::FixedAdvanceMeasurer};
+
+    const SOURCE: &str = r#"<capsule>
+    <meta>
+        <title>layout</title>
+    </meta>
+    <view flexdir="column" align="flex_start">
+        <obj id="outer" padding="10">
+            <obj id="inner" width="50%" height="20" />
+        </obj>
+        <obj id="sibling" width="30" height="calc(50% - 10)" />
+    </view>
+</capsule>"#;
+
+    fn find(capsule: &Capsule, id: &str) -> BoxedCapsuleObject {
+        let mut found = None;
+
+        crate::capsule::obj::iter_all_objects(capsule, |o| {
+            if o.map(|o| o.base().id.read().as_deref() == Some(id)) {
+                found = Some(o.map(Clone::clone));
+            }
+        });
 
-    let root_base = capsule.view.base();
-    let mut root_children_nodes = Vec::new();
-    for child in root_base.children.iter() {
-        root_children_nodes.push(build_node(&mut stretch, child));
+        found.unwrap()
     }
 
-    // HACK: this is just so we can get a [`ConcurrentElement`] to pass
-    // to [`styling_to_stretch`]
-    let root_: ConcurrentVec<BoxedCapsuleObject> = ConcurrentVec::new();
-    let arc = Arc::new(capsule.view.clone());
-    root_.push(arc);
-    let mut root_style = styling_to_stretch(root_.get(0).unwrap());
+    #[test]
+    fn style_changes_only_relayout_what_changed() {
+        let (mut capsule, _) = parse_capsule(SOURCE).unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let mut tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let outer = find(&capsule, "outer");
+        let inner = find(&capsule, "inner");
+        let sibling = find(&capsule, "sibling");
 
-    #[allow(clippy::cast_precision_loss)]
-    {
-        root_style.size = Size {
-            width: Dimension::Points(WINDOW_WIDTH as f32),
-            height: Dimension::Points(WINDOW_HEIGHT as f32),
+        assert_eq!(outer.bounding_box().w, 20.0);
+        assert_eq!(sibling.bounding_box().h, 290.0);
+
+        let laid_out = |tree: &LayoutTree, object: &BoxedCapsuleObject| {
+            let base = object.base();
+            let index = base.style.read().layout_hook().map(LayoutHook::index);
+            tree.entries[index.unwrap()].laid_out
         };
+        let before: Vec<_> = [&outer, &inner, &sibling]
+            .map(|object| laid_out(&tree, object))
+            .into();
+
+        {
+            let base = outer.base();
+            let mut style = base.style.write();
+            style.width = Some(CODimension::Points(200.0));
+            style.set_dirty();
+        }
+        assert!(outer.is_dirty());
+
+        tree.update();
+
+        assert!(tree.changes.take().is_empty());
+        assert!(!outer.is_dirty());
+        assert_eq!(outer.bounding_box().w, 200.0);
+        assert_eq!(inner.bounding_box().w, 90.0);
+        assert_eq!(inner.bounding_box().x, 10.0);
+        assert_eq!(sibling.bounding_box().y, 40.0);
+
+        // the sibling kept its size and place, so it wasn't touched
+        assert!(laid_out(&tree, &outer) > before[0]);
+        assert!(laid_out(&tree, &inner) > before[1]);
+        assert_eq!(laid_out(&tree, &sibling), before[2]);
+
+        // nothing that decides the paint order changed
+        tree.restack = false;
+        {
+            let base = inner.base();
+            let mut style = base.style.write();
+            style.height = Some(CODimension::Points(30.0));
+            style.set_dirty();
+        }
+        for index in tree.changes.take().restyled {
+            tree.restyle(index);
+        }
+        assert!(!tree.restack);
     }
 
-    let root_node = stretch.new_node(root_style, root_children_nodes).unwrap();
+    #[test]
+    fn resizing_follows_the_viewport() {
+        let (mut capsule, _) = parse_capsule(SOURCE).unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let mut tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let sibling = find(&capsule, "sibling");
 
-    stretch
-        .compute_layout(root_node, Size::undefined())
+        tree.resize(Vec2::new(400.0, 300.0));
+
+        assert_eq!(capsule.view.bounding_box().w, 400.0);
+        assert_eq!(sibling.bounding_box().h, 140.0);
+    }
+
+    #[test]
+    fn scrolling_moves_and_clips_children() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>scroll</title>
+    </meta>
+    <view flexdir="column" align="flex_start">
+        <obj id="list" flexdir="column" height="100" border_width="5" overflow="scroll">
+            <obj id="first" width="50" height="40" />
+            <obj id="second" width="50" height="40" />
+            <obj width="50" height="40" />
+            <obj width="50" height="40" />
+        </obj>
+        <obj id="other" width="10" height="10" />
+    </view>
+</capsule>"#,
+        )
         .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let mut tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let list = find(&capsule, "list");
+        let first = find(&capsule, "first");
+        let second = find(&capsule, "second");
+
+        assert_eq!(
+            list.base().computed_style.read().max_scroll,
+            Vec2::new(0.0, 70.0)
+        );
+
+        list.base().set_scroll(Vec2::new(0.0, 500.0));
+        tree.update();
 
-    let root_child_nodes = stretch.children(root_node).unwrap();
-    for (child_node, child) in root_child_nodes.into_iter().zip(root_base.children.iter()) {
-        apply_layout(&stretch, child_node, child, 0.0, 0.0);
+        assert_eq!(*list.base().scroll.read(), Vec2::new(0.0, 70.0));
+        assert_eq!(first.bounding_box().y, -65.0);
+        assert!(!second.hit_test(Vec2::new(10.0, 2.0)));
+        assert!(second.hit_test(Vec2::new(10.0, 10.0)));
+
+        // a restyle elsewhere in the same frame doesn't lose the scroll
+        list.base().set_scroll(Vec2::ZERO);
+        {
+            let other = find(&capsule, "other");
+            let base = other.base();
+            let mut style = base.style.write();
+            style.width = Some(CODimension::Points(20.0));
+            style.set_dirty();
+        }
+        tree.update();
+
+        assert_eq!(first.bounding_box().y, 5.0);
+        assert!(second.hit_test(Vec2::new(10.0, 50.0)));
+    }
+
+    #[test]
+    fn scrolled_content_only_shrinks_when_asked_to() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>shrink</title>
+    </meta>
+    <view flexdir="column" align="flex_start">
+        <obj flexdir="column" height="60" overflow="scroll">
+            <obj id="kept" width="50" height="40" />
+            <obj id="shrunk" width="50" height="40" flex_shrink="1" />
+        </obj>
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let _tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+
+        assert_eq!(find(&capsule, "kept").bounding_box().h, 40.0);
+        assert_eq!(find(&capsule, "shrunk").bounding_box().h, 20.0);
+    }
+
+    #[test]
+    fn grid_items_fill_their_cells() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>grid</title>
+    </meta>
+    <view flexdir="column" align="flex_start">
+        <obj id="grid" display="grid" width="410" padding="10" gap="10" grid_template_columns="100 1fr 2fr">
+            <obj id="a" />
+            <obj id="b" grid_column="span 2" height="50" />
+            <obj id="c" width="40" height="20" />
+        </obj>
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let _tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let bounds = |id| find(&capsule, id).bounding_box();
+
+        assert_eq!(bounds("a"), Rect::new(10.0, 10.0, 100.0, 50.0));
+        assert_eq!(bounds("b"), Rect::new(120.0, 10.0, 280.0, 50.0));
+        assert_eq!(bounds("c"), Rect::new(10.0, 70.0, 40.0, 20.0));
+        assert_eq!(bounds("grid").h, 100.0);
+    }
+
+    #[test]
+    fn grid_items_keep_their_position() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>grid</title>
+    </meta>
+    <view flexdir="column" align="flex_start">
+        <obj display="grid" width="220" padding="10" grid_template_columns="100 100">
+            <obj id="moved" height="20" left="5" bottom="4" />
+            <obj id="badge" position="absolute" right="0" top="0" width="8" height="8" />
+            <obj id="next" height="20" />
+        </obj>
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let _tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let bounds = |id| find(&capsule, id).bounding_box();
+
+        assert_eq!(bounds("moved"), Rect::new(15.0, 6.0, 100.0, 20.0));
+        // takes no cell, it's placed by its insets like in a flex container
+        assert_eq!(bounds("badge"), Rect::new(212.0, 0.0, 8.0, 8.0));
+        assert_eq!(bounds("next"), Rect::new(110.0, 10.0, 100.0, 20.0));
+    }
+
+    #[test]
+    fn transforms_leave_the_layout_alone() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>transform</title>
+    </meta>
+    <view flexdir="column" align="flex_start">
+        <obj id="label" width="100" height="20" transform="rotate(90deg)">
+            <obj id="child" width="10" height="10" transform="translate(100%, 0)" />
+        </obj>
+        <obj id="next" width="10" height="10" />
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let _tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let label = find(&capsule, "label");
+        let child = find(&capsule, "child");
+
+        assert_eq!(find(&capsule, "next").bounding_box().y, 20.0);
+        assert_eq!(label.bounding_box(), Rect::new(0.0, 0.0, 100.0, 20.0));
+        assert!(label.hit_test(Vec2::new(50.0, 50.0)));
+        assert!(!label.hit_test(Vec2::new(5.0, 5.0)));
+
+        // moved right by its width, then turned with its parent
+        assert!(child.hit_test(Vec2::new(55.0, -25.0)));
+        assert!(!child.hit_test(Vec2::new(15.0, 5.0)));
+    }
+
+    #[test]
+    fn text_wraps_to_its_box() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>text</title>
+    </meta>
+    <view flexdir="column" align="flex_start">
+        <obj width="100">
+            <text id="fixed" font_size="10">the quick brown fox jumps</text>
+        </obj>
+        <obj width="60" flexdir="column" align="flex_start">
+            <text id="hinted" font_size="10" padding="2">aaaa bbbb cccc</text>
+        </obj>
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let _tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let lines = |id| {
+            let object = find(&capsule, id);
+            let lines = object
+                .as_any()
+                .downcast_ref::<CSText>()
+                .unwrap()
+                .lines
+                .read()
+                .iter()
+                .map(TextLine::text)
+                .collect::<Vec<_>>();
+            (lines, object.bounding_box())
+        };
+
+        let (fixed, bounds) = lines("fixed");
+        assert_eq!(fixed, ["the quick brown fox", "jumps"]);
+        assert_eq!(bounds.h, 20.0);
+
+        // its width is `auto`, so stretch only knows where to wrap it from
+        // the deferred pass
+        let (hinted, bounds) = lines("hinted");
+        assert_eq!(hinted, ["aaaa bbbb", "cccc"]);
+        assert_eq!((bounds.w, bounds.h), (49.0, 24.0));
+    }
+
+    #[test]
+    fn test_capsule_lays_out_headlessly() {
+        let (mut capsule, _) = parse_capsule(include_str!("../../test.capsule")).unwrap();
+        let measurer = FixedAdvanceMeasurer::default();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let mut tree = LayoutTree::new(&capsule, Arc::new(measurer));
+        tree.resize(Vec2::new(320.0, 240.0));
+
+        assert_eq!(
+            capsule.view.bounding_box(),
+            Rect::new(0.0, 0.0, 320.0, 240.0)
+        );
+
+        crate::capsule::obj::iter_all_objects(&capsule, |o| {
+            o.map(|o| {
+                let Some(text) = o.as_any().downcast_ref::<CSText>() else {
+                    return;
+                };
+                let computed = o.base().computed_style.read().clone();
+                let lines = text.lines.read();
+
+                assert!(!lines.is_empty());
+                for line in lines.iter().map(TextLine::text) {
+                    let width = measurer.line_width(
+                        &line,
+                        computed.font.as_ref(),
+                        computed.font_size.into(),
+                    );
+                    assert!(
+                        width <= computed.content_box().w + 0.5 || !line.contains(' '),
+                        "{line:?} overflows {computed:?}"
+                    );
+                }
+            });
+        });
     }
 }
@@ -1,27 +1,70 @@
-use crate::{
-    capsule::{
-        Capsule,
-        obj::{ArcLock, iter_all_objects},
-    },
-    layout::computer::compute_layout,
-};
-
-pub fn update_layout(capsule: &ArcLock<Capsule>) {
-    let mut is_dirty = false;
-
-    {
-        iter_all_objects(&capsule.read(), |e| {
-            if e.map(|e| e.is_dirty()) {
-                is_dirty = true;
-
-                e.map(|e| {
-                    e.set_non_dirty();
-                });
-            }
-        });
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+/// Objects that changed since the layout tree last looked, by index, so it
+/// doesn't have to search the whole capsule for them
+#[derive(Debug, Default)]
+pub struct PendingChanges {
+    /// objects whose style changed, these need to be laid out again
+    pub restyled: Vec<usize>,
+    /// objects whose scroll offset changed, only their descendants move
+    pub scrolled: Vec<usize>,
+}
+
+impl PendingChanges {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.restyled.is_empty() && self.scrolled.is_empty()
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LayoutChanges(Arc<Mutex<PendingChanges>>);
+
+impl LayoutChanges {
+    #[must_use]
+    pub fn hook(&self, index: usize) -> LayoutHook {
+        LayoutHook {
+            changes: self.clone(),
+            index,
+        }
+    }
+
+    /// Takes every change reported so far, each index at most once
+    #[must_use]
+    pub fn take(&self) -> PendingChanges {
+        let mut changes = std::mem::take(&mut *self.0.lock());
+
+        for indices in [&mut changes.restyled, &mut changes.scrolled] {
+            indices.sort_unstable();
+            indices.dedup();
+        }
+
+        changes
+    }
+}
+
+/// Lets a [`Styling`](crate::layout::styling::Styling) tell the layout tree
+/// it belongs to that its object changed
+#[derive(Debug, Clone)]
+pub struct LayoutHook {
+    changes: LayoutChanges,
+    index: usize,
+}
+
+impl LayoutHook {
+    /// Index of the object in the layout tree
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn notify(&self) {
+        self.changes.0.lock().restyled.push(self.index);
     }
 
-    if is_dirty {
-        compute_layout(&mut capsule.write());
+    pub fn notify_scroll(&self) {
+        self.changes.0.lock().scrolled.push(self.index);
     }
 }
@@ -0,0 +1,93 @@
+use std::{fmt, path::Path};
+
+use anyhow::Context;
+use fontdue::{Font, FontSettings};
+
+/// A font file a capsule declared with `<font>`, loaded so text can be
+/// measured and drawn with it
+pub struct FontFace {
+    bytes: Vec<u8>,
+    font: Font,
+}
+
+impl FontFace {
+    /// Parses a TrueType or OpenType font
+    pub fn from_bytes(bytes: Vec<u8>) -> anyhow::Result<Self> {
+        let font = Font::from_bytes(bytes.as_slice(), FontSettings::default())
+            .map_err(anyhow::Error::msg)?;
+
+        Ok(Self { bytes, font })
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let bytes =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+
+        Self::from_bytes(bytes).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    /// The font file, for renderers that parse it themselves
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[must_use]
+    pub const fn fontdue(&self) -> &Font {
+        &self.font
+    }
+}
+
+/// Faces are the same when they're the same load of a font, which is
+/// shared through `Arc`s. Comparing the files would make every layout
+/// change check go through them byte by byte.
+impl PartialEq for FontFace {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for FontFace {}
+
+impl fmt::Debug for FontFace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FontFace")
+            .field("name", &self.font.name())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Bold and italic made up from a font's regular glyphs, for text in `<b>`
+/// and `<i>`: fonts are declared one file each, without variants
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct FontSynthesis {
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl FontSynthesis {
+    /// How far right italic glyphs lean for every pixel above the baseline
+    pub const ITALIC_SLANT: f32 = 0.2;
+
+    /// Bold text is drawn a second time this much further right, which
+    /// also makes it this much wider
+    #[must_use]
+    pub fn bold_offset(self, font_size: f32) -> f32 {
+        if self.bold {
+            (font_size / 16.0).round().max(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// How far italic text can reach past either end of its advance
+    #[must_use]
+    pub fn italic_lean(self, font_size: f32) -> f32 {
+        if self.italic {
+            font_size * Self::ITALIC_SLANT
+        } else {
+            0.0
+        }
+    }
+}
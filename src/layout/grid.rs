@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+
+use crate::layout::capsule::{
+    dimension::{CODimension, DimensionContext},
+    grid::{COGridPlacement, COGridTrack},
+};
+
+/// The cells an item covers, 0-based
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GridArea {
+    pub column: usize,
+    pub row: usize,
+    pub column_span: usize,
+    pub row_span: usize,
+}
+
+impl GridArea {
+    #[must_use]
+    pub const fn column_end(&self) -> usize {
+        self.column + self.column_span
+    }
+
+    #[must_use]
+    pub const fn row_end(&self) -> usize {
+        self.row + self.row_span
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.column..self.column_end())
+            .flat_map(move |column| (self.row..self.row_end()).map(move |row| (column, row)))
+    }
+}
+
+/// Places every item, given as its `(grid_column, grid_row)`, into a grid
+/// that has `columns` explicit columns and `rows` explicit rows. Items
+/// without a position fill the first free cells row by row, after the cells
+/// of the items placed before them.
+#[must_use]
+pub fn place_items(
+    columns: usize,
+    rows: usize,
+    placements: &[(COGridPlacement, COGridPlacement)],
+) -> Vec<GridArea> {
+    let mut occupied = HashSet::new();
+    let mut cursor = (0, 0);
+    let mut areas = Vec::with_capacity(placements.len());
+
+    for (column, row) in placements {
+        let (column, column_span) = column.resolve(columns);
+        let (row, row_span) = row.resolve(rows);
+        let fits = |column: usize, row: usize, occupied: &HashSet<(usize, usize)>| {
+            let area = GridArea {
+                column,
+                row,
+                column_span,
+                row_span,
+            };
+
+            area.cells().all(|cell| !occupied.contains(&cell))
+        };
+        let last_column = columns.max(1).saturating_sub(column_span);
+
+        let (column, row) = match (column, row) {
+            (Some(column), Some(row)) => (column, row),
+            (Some(column), None) => {
+                let row = (0..).find(|row| fits(column, *row, &occupied)).unwrap();
+                (column, row)
+            }
+            (None, Some(row)) => {
+                let column = (0..=last_column)
+                    .find(|column| fits(*column, row, &occupied))
+                    .unwrap_or(0);
+                (column, row)
+            }
+            (None, None) => {
+                let (mut column, mut row) = cursor;
+
+                loop {
+                    if column > last_column {
+                        (column, row) = (0, row + 1);
+                    } else if fits(column, row, &occupied) {
+                        break;
+                    } else {
+                        column += 1;
+                    }
+                }
+
+                cursor = (column + column_span, row);
+                (column, row)
+            }
+        };
+
+        let area = GridArea {
+            column,
+            row,
+            column_span,
+            row_span,
+        };
+
+        occupied.extend(area.cells());
+        areas.push(area);
+    }
+
+    areas
+}
+
+/// Sizes `count` tracks, the ones past the end of `tracks` are `auto`.
+/// `natural` returns the size the items that only cover track `i` would
+/// like to have. Percentages and fractions need the `available` space,
+/// without it they're sized like `auto`.
+#[must_use]
+pub fn size_tracks<F>(
+    tracks: &[COGridTrack],
+    count: usize,
+    available: Option<f32>,
+    gap: f32,
+    ctx: &DimensionContext,
+    natural: F,
+) -> Vec<f32>
+where
+    F: Fn(usize) -> f32,
+{
+    let mut sizes = vec![0.0; count];
+    let mut fractions = Vec::new();
+
+    for (i, size) in sizes.iter_mut().enumerate() {
+        let track = tracks.get(i).copied().unwrap_or(COGridTrack::Auto);
+
+        match (track, available) {
+            (COGridTrack::Fixed(dimension), Some(available)) => {
+                *size = dimension.resolve(ctx, available);
+            }
+            (COGridTrack::Fixed(dimension), None) if !has_percent(dimension) => {
+                *size = dimension.resolve(ctx, 0.0);
+            }
+            (COGridTrack::Fraction(fraction), Some(_)) => fractions.push((i, fraction)),
+            _ => *size = natural(i),
+        }
+    }
+
+    if let Some(available) = available
+        && !fractions.is_empty()
+    {
+        #[allow(clippy::cast_precision_loss)]
+        let gaps = gap * count.saturating_sub(1) as f32;
+        let free = (available - gaps - sizes.iter().sum::<f32>()).max(0.0);
+        // like css, fractions that add up to less than 1 leave space unused
+        let total = fractions.iter().map(|(_, f)| f).sum::<f32>().max(1.0);
+
+        for (i, fraction) in fractions {
+            sizes[i] = free * fraction / total;
+        }
+    }
+
+    sizes
+}
+
+fn has_percent(dimension: CODimension) -> bool {
+    match dimension {
+        CODimension::Percent(_) => true,
+        CODimension::Calc(calc) => calc.percent != 0.0,
+        _ => false,
+    }
+}
+
+/// Where each track starts, relative to the first one
+#[must_use]
+pub fn track_offsets(sizes: &[f32], gap: f32) -> Vec<f32> {
+    sizes
+        .iter()
+        .scan(0.0, |offset, size| {
+            let start = *offset;
+            *offset += size + gap;
+            Some(start)
+        })
+        .collect()
+}
+
+/// Size of `span` tracks starting at `start`, including the gaps between them
+#[must_use]
+pub fn span_size(sizes: &[f32], start: usize, span: usize, gap: f32) -> f32 {
+    #[allow(clippy::cast_precision_loss)]
+    let gaps = gap * span.saturating_sub(1) as f32;
+
+    sizes[start..start + span].iter().sum::<f32>() + gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capsule::parser::try_parse_grid_placement;
+
+    const CTX: DimensionContext = DimensionContext {
+        font_size: 16.0,
+        root_font_size: 16.0,
+        viewport_width: 800.0,
+        viewport_height: 600.0,
+    };
+
+    fn at(start: i16, span: u16) -> COGridPlacement {
+        COGridPlacement {
+            start: Some(start),
+            end: None,
+            span,
+        }
+    }
+
+    #[test]
+    fn items_fill_free_cells_row_by_row() {
+        let auto = COGridPlacement::default();
+        let wide = COGridPlacement {
+            start: None,
+            end: None,
+            span: 2,
+        };
+        let areas = place_items(
+            3,
+            0,
+            &[
+                (at(2, 1), at(1, 1)),
+                (auto, auto),
+                (wide, auto),
+                (auto, at(1, 1)),
+            ],
+        );
+        let cells: Vec<_> = areas.iter().map(|a| (a.column, a.row)).collect();
+
+        assert_eq!(cells, [(1, 0), (0, 0), (0, 1), (2, 0)]);
+        assert_eq!(areas[2].column_end(), 2);
+    }
+
+    #[test]
+    fn negative_lines_count_from_the_end() {
+        let placement = |text| try_parse_grid_placement(text).unwrap();
+        let areas = place_items(
+            3,
+            2,
+            &[
+                (placement("1 / -1"), placement("-2")),
+                (placement("-2 / 1"), placement("1 / -1")),
+                (placement("-1"), placement("1")),
+                (placement("-9 / -1"), placement("auto")),
+            ],
+        );
+        let cells: Vec<_> = areas
+            .iter()
+            .map(|a| (a.column, a.column_span, a.row, a.row_span))
+            .collect();
+
+        assert_eq!(
+            cells,
+            [(0, 3, 1, 1), (0, 2, 0, 2), (3, 1, 0, 1), (0, 3, 2, 1)]
+        );
+        assert_eq!(placement("1 / -1").as_text(), "1 / -1");
+        assert_eq!(placement("2 / 4").as_text(), "2 / span 2");
+        assert_eq!(try_parse_grid_placement("3 / 1"), None);
+        assert_eq!(try_parse_grid_placement("0"), None);
+    }
+
+    #[test]
+    fn fractions_share_what_is_left() {
+        let tracks = [
+            COGridTrack::Fixed(CODimension::Points(100.0)),
+            COGridTrack::Fraction(1.0),
+            COGridTrack::Auto,
+            COGridTrack::Fraction(3.0),
+        ];
+        let sizes = size_tracks(&tracks, 5, Some(580.0), 10.0, &CTX, |i| i as f32 * 10.0);
+
+        assert_eq!(sizes, [100.0, 95.0, 20.0, 285.0, 40.0]);
+        assert_eq!(track_offsets(&sizes, 10.0)[3], 245.0);
+        assert_eq!(span_size(&sizes, 1, 2, 10.0), 125.0);
+
+        let sizes = size_tracks(&tracks, 2, None, 10.0, &CTX, |_| 7.0);
+        assert_eq!(sizes, [100.0, 7.0]);
+    }
+}
@@ -2,4 +2,8 @@ pub mod capsule;
 pub mod computed;
 pub mod computer;
 pub mod dirty;
+pub mod font;
+pub mod grid;
+pub mod stacking;
 pub mod styling;
+pub mod text;
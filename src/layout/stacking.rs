@@ -0,0 +1,269 @@
+use std::sync::Arc;
+
+use macroquad::math::Vec2;
+
+use crate::{
+    capsule::{Capsule, obj::BoxedCapsuleObject},
+    layout::capsule::display::CODisplay,
+};
+
+struct StackedObject {
+    object: BoxedCapsuleObject,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+/// The order the objects of a capsule are painted in, back to front.
+///
+/// The root view and every object with a `z_index` are stacking contexts.
+/// A stacking context paints itself first, then the objects inside of it
+/// ordered by `z_index`. Objects without one count as 0 and are painted in
+/// tree order, while nested stacking contexts are painted all at once, so
+/// nothing from outside can end up in between their objects.
+///
+/// Objects with `display="none"` are left out together with their children,
+/// hidden ones are kept but neither painted nor hit.
+pub struct StackingOrder {
+    /// in tree order, the root view comes first
+    objects: Vec<StackedObject>,
+    /// indices into `objects`
+    order: Vec<usize>,
+}
+
+impl StackingOrder {
+    #[must_use]
+    pub fn new(capsule: &Capsule) -> Self {
+        Self::of(Arc::new(capsule.view.clone()))
+    }
+
+    /// The paint order of `root` and the objects inside of it
+    #[must_use]
+    pub fn of(root: BoxedCapsuleObject) -> Self {
+        let mut stacking = Self {
+            objects: Vec::new(),
+            order: Vec::new(),
+        };
+
+        let root = stacking.collect(root, None);
+        stacking.order = stacking.stack(root);
+        stacking
+    }
+
+    fn collect(&mut self, object: BoxedCapsuleObject, parent: Option<usize>) -> usize {
+        let index = self.objects.len();
+        let children = object.base().children_vec();
+
+        self.objects.push(StackedObject {
+            object,
+            parent,
+            children: Vec::new(),
+        });
+
+        for child in children {
+            if child.base().style.read().display == CODisplay::None {
+                continue;
+            }
+
+            let child = self.collect(child, Some(index));
+            self.objects[index].children.push(child);
+        }
+
+        index
+    }
+
+    fn is_visible(&self, index: usize) -> bool {
+        self.objects[index]
+            .object
+            .base()
+            .computed_style
+            .read()
+            .visible
+    }
+
+    fn z_index(&self, index: usize) -> Option<i32> {
+        self.objects[index].object.base().style.read().z_index
+    }
+
+    /// Paint order of the stacking context at `index`
+    fn stack(&self, index: usize) -> Vec<usize> {
+        let mut layers = Vec::new();
+        self.collect_layers(index, &mut layers);
+
+        // stable, so equal `z_index`es keep their tree order
+        layers.sort_by_key(|(z_index, _)| *z_index);
+
+        std::iter::once(index)
+            .chain(layers.into_iter().flat_map(|(_, layer)| layer))
+            .collect()
+    }
+
+    fn collect_layers(&self, index: usize, layers: &mut Vec<(i32, Vec<usize>)>) {
+        for &child in &self.objects[index].children {
+            match self.z_index(child) {
+                Some(z_index) => layers.push((z_index, self.stack(child))),
+                None => {
+                    layers.push((0, vec![child]));
+                    self.collect_layers(child, layers);
+                }
+            }
+        }
+    }
+
+    /// Every visible object, back to front
+    pub fn paint_order(&self) -> impl Iterator<Item = &BoxedCapsuleObject> {
+        self.order
+            .iter()
+            .filter(|i| self.is_visible(**i))
+            .map(|i| &self.objects[*i].object)
+    }
+
+    /// The topmost object at `point` followed by its ancestors, up to the
+    /// root view. Empty when nothing is there, not even the root view.
+    #[must_use]
+    pub fn hit_path(&self, point: Vec2) -> Vec<BoxedCapsuleObject> {
+        let mut path = Vec::new();
+        let mut target = self
+            .order
+            .iter()
+            .rev()
+            .find(|i| self.is_visible(**i) && self.objects[**i].object.hit_test(point))
+            .copied();
+
+        while let Some(index) = target {
+            path.push(self.objects[index].object.clone());
+            target = self.objects[index].parent;
+        }
+
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        capsule::{obj::CapsuleObject, parser::parse_capsule},
+        layout::{
+            capsule::visibility::COVisibility, computer::LayoutTree, text::FixedAdvanceMeasurer,
+        },
+    };
+
+    fn id(object: &BoxedCapsuleObject) -> String {
+        object.base().id.read().clone().unwrap_or_default()
+    }
+
+    #[test]
+    fn z_index_decides_paint_and_hit_order() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>stacking</title>
+    </meta>
+    <view id="root">
+        <obj id="popup" position="absolute" z_index="2" width="100" height="100">
+            <obj id="button" width="50" height="50" />
+        </obj>
+        <obj id="page" width="200" height="200">
+            <obj id="raised" z_index="5" width="10" height="10" />
+        </obj>
+        <obj id="under" position="absolute" z_index="-1" width="300" height="300" />
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let _tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let stacking = StackingOrder::new(&capsule);
+
+        let order: Vec<_> = stacking.paint_order().map(id).collect();
+        assert_eq!(
+            order,
+            ["root", "under", "page", "popup", "button", "raised"]
+        );
+
+        let path: Vec<_> = stacking
+            .hit_path(Vec2::new(20.0, 20.0))
+            .iter()
+            .map(id)
+            .collect();
+        assert_eq!(path, ["button", "popup", "root"]);
+
+        let path: Vec<_> = stacking
+            .hit_path(Vec2::new(5.0, 5.0))
+            .iter()
+            .map(id)
+            .collect();
+        assert_eq!(path, ["raised", "page", "root"]);
+
+        let path: Vec<_> = stacking
+            .hit_path(Vec2::new(250.0, 250.0))
+            .iter()
+            .map(id)
+            .collect();
+        assert_eq!(path, ["under", "root"]);
+    }
+
+    #[test]
+    fn hidden_objects_are_skipped() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>hiding</title>
+    </meta>
+    <view id="root" flexdir="column">
+        <obj id="tab" height="50" visibility="hidden">
+            <obj id="label" width="50" height="50" />
+        </obj>
+        <obj id="panel" height="50" display="none">
+            <obj id="content" width="50" height="50" />
+        </obj>
+        <obj id="footer" height="50" />
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let mut tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        let find = |id: &str| {
+            StackingOrder::new(&capsule)
+                .objects
+                .iter()
+                .find(|o| self::id(&o.object) == id)
+                .map(|o| o.object.clone())
+        };
+
+        // the hidden tab keeps its space, the panel doesn't take any
+        let footer = find("footer").unwrap();
+        assert_eq!(footer.base().computed_style.read().y, 50.0);
+        assert!(find("content").is_none());
+
+        let stacking = StackingOrder::new(&capsule);
+        let order: Vec<_> = stacking.paint_order().map(id).collect();
+        assert_eq!(order, ["root", "footer"]);
+
+        let path: Vec<_> = stacking
+            .hit_path(Vec2::new(20.0, 20.0))
+            .iter()
+            .map(id)
+            .collect();
+        assert_eq!(path, ["root"]);
+
+        for child in capsule.view.base().children_vec() {
+            let base = child.base();
+            let mut style = base.style.write();
+            style.visibility = COVisibility::Visible;
+            style.display = CODisplay::Flex;
+            style.set_dirty();
+        }
+        tree.update();
+
+        assert_eq!(footer.base().computed_style.read().y, 100.0);
+
+        let path: Vec<_> = StackingOrder::new(&capsule)
+            .hit_path(Vec2::new(20.0, 70.0))
+            .iter()
+            .map(id)
+            .collect();
+        assert_eq!(path, ["content", "panel", "root"]);
+    }
+}
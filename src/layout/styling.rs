@@ -6,13 +6,38 @@ use serde::{Deserialize, Serialize};
 use crate::{
     capsule::{
         obj::ArcLock,
-        parser::{try_parse_color, try_parse_dimension},
+        parser::{
+            try_parse_box_shadow, try_parse_color, try_parse_corners, try_parse_dimension,
+            try_parse_edges, try_parse_font_family, try_parse_grid_placement,
+            try_parse_grid_tracks, try_parse_transform, try_parse_transform_origin,
+            valid_flex_factor, valid_ratio,
+        },
     },
-    layout::capsule::{
-        align::COAlignItems, color::COColor, dimension::CODimension, flexdir::COFlexDirection,
-        justify::COJustifyContent,
+    layout::{
+        capsule::{
+            align::COAlignItems,
+            align_content::COAlignContent,
+            align_self::COAlignSelf,
+            border_style::COBorderStyle,
+            color::COColor,
+            corners::COCorners,
+            dimension::CODimension,
+            display::CODisplay,
+            edges::COEdges,
+            flexdir::COFlexDirection,
+            font_family::COFontFamily,
+            grid::{COGridPlacement, COGridTracks},
+            justify::COJustifyContent,
+            object_fit::COObjectFit,
+            overflow::COOverflow,
+            position::COPosition,
+            shadow::COBoxShadow,
+            transform::{COTransform, COTransformOrigin},
+            visibility::COVisibility,
+            wrap::COFlexWrap,
+        },
+        dirty::LayoutHook,
     },
-    renderer::constants::DEFAULT_TEXT_SIZE,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,14 +45,64 @@ pub struct Styling {
     pub align: COAlignItems,
     pub justify: COJustifyContent,
     pub flexdir: COFlexDirection,
+    pub flex_wrap: COFlexWrap,
+    pub align_self: COAlignSelf,
+    pub align_content: COAlignContent,
+    pub flex_grow: f32,
+    /// `None` shrinks like 1, except in containers that clip their overflow
+    pub flex_shrink: Option<f32>,
+    pub flex_basis: Option<CODimension>,
 
     pub width: Option<CODimension>,
     pub height: Option<CODimension>,
+    pub min_width: Option<CODimension>,
+    pub max_width: Option<CODimension>,
+    pub min_height: Option<CODimension>,
+    pub max_height: Option<CODimension>,
+    /// width divided by height
+    pub aspect_ratio: Option<f32>,
+    /// how an image is sized to the content box
+    pub object_fit: COObjectFit,
     pub color: Option<COColor>,
     pub background_color: Option<COColor>,
-    pub font_size: u16,
+    pub font_size: Option<u16>,
+    pub font_family: COFontFamily,
+
+    pub margin: COEdges,
+    pub padding: COEdges,
+    pub border_width: COEdges,
+    pub border_color: Option<COColor>,
+    pub border_style: COBorderStyle,
+    pub border_radius: COCorners,
+    pub box_shadow: COBoxShadow,
+
+    pub position: COPosition,
+    pub top: Option<CODimension>,
+    pub right: Option<CODimension>,
+    pub bottom: Option<CODimension>,
+    pub left: Option<CODimension>,
+    /// paint order within the closest ancestor stacking context, setting it
+    /// makes the object a stacking context of its own
+    pub z_index: Option<i32>,
+
+    pub overflow: COOverflow,
+
+    pub display: CODisplay,
+    pub visibility: COVisibility,
+    pub grid_template_columns: COGridTracks,
+    pub grid_template_rows: COGridTracks,
+    pub row_gap: Option<CODimension>,
+    pub column_gap: Option<CODimension>,
+    /// where the object goes when its parent is a grid
+    pub grid_column: COGridPlacement,
+    pub grid_row: COGridPlacement,
+
+    pub transform: COTransform,
+    pub transform_origin: COTransformOrigin,
 
     dirty: bool,
+    #[serde(skip)]
+    layout_hook: Option<LayoutHook>,
 }
 
 impl Styling {
@@ -36,13 +111,29 @@ impl Styling {
         self.dirty
     }
 
-    pub const fn set_dirty(&mut self) {
+    /// Marks the style as changed, notifying the layout tree the first time
+    pub fn set_dirty(&mut self) {
+        if !self.dirty
+            && let Some(hook) = &self.layout_hook
+        {
+            hook.notify();
+        }
+
         self.dirty = true;
     }
 
     pub const fn set_non_dirty(&mut self) {
         self.dirty = false;
     }
+
+    pub fn set_layout_hook(&mut self, hook: LayoutHook) {
+        self.layout_hook = Some(hook);
+    }
+
+    #[must_use]
+    pub const fn layout_hook(&self) -> Option<&LayoutHook> {
+        self.layout_hook.as_ref()
+    }
 }
 
 impl Default for Styling {
@@ -51,12 +142,50 @@ impl Default for Styling {
             align: COAlignItems::default(),
             justify: COJustifyContent::default(),
             flexdir: COFlexDirection::default(),
+            flex_wrap: COFlexWrap::default(),
+            align_self: COAlignSelf::default(),
+            align_content: COAlignContent::default(),
+            flex_grow: 0.0,
+            flex_shrink: None,
+            flex_basis: None,
             width: None,
             height: None,
-            font_size: DEFAULT_TEXT_SIZE,
+            min_width: None,
+            max_width: None,
+            min_height: None,
+            max_height: None,
+            aspect_ratio: None,
+            object_fit: COObjectFit::default(),
             color: None,
             background_color: None,
+            font_size: None,
+            font_family: COFontFamily::default(),
+            margin: COEdges::default(),
+            padding: COEdges::default(),
+            border_width: COEdges::default(),
+            border_color: None,
+            border_style: COBorderStyle::default(),
+            border_radius: COCorners::default(),
+            box_shadow: COBoxShadow::default(),
+            position: COPosition::default(),
+            top: None,
+            right: None,
+            bottom: None,
+            left: None,
+            z_index: None,
+            overflow: COOverflow::default(),
+            display: CODisplay::default(),
+            visibility: COVisibility::default(),
+            grid_template_columns: COGridTracks::default(),
+            grid_template_rows: COGridTracks::default(),
+            row_gap: None,
+            column_gap: None,
+            grid_column: COGridPlacement::default(),
+            grid_row: COGridPlacement::default(),
+            transform: COTransform::default(),
+            transform_origin: COTransformOrigin::default(),
             dirty: false,
+            layout_hook: None,
         }
     }
 }
@@ -83,9 +212,15 @@ macro_rules! impl_setget_dimension {
             Ok(Value::Nil)
         });
 
-        $fields.add_field_method_set(stringify!($name), |_lua, this, v: String| {
-            this.0.write().$name = try_parse_dimension(&v);
-            this.0.write().set_dirty();
+        // setting nil clears the property
+        $fields.add_field_method_set(stringify!($name), |_lua, this, v: Option<String>| {
+            let value = v
+                .map(|v| {
+                    try_parse_dimension(&v)
+                        .context(format!("failed to parse {}", stringify!($name)))
+                })
+                .transpose()?;
+            this.write().$name = value;
             Ok(())
         });
     };
@@ -109,14 +244,45 @@ macro_rules! impl_setget_enum {
     };
 }
 
+/// `$valid` only lets through what the parser would take, returning `None`
+/// for the rest
 macro_rules! impl_setget_primitive {
     ($fields: ident, $name: ident, $type: tt, $lua_type: tt) => {
+        impl_setget_primitive!($fields, $name, $type, $lua_type, Some);
+    };
+    ($fields: ident, $name: ident, $type: tt, $lua_type: tt, $valid: expr) => {
         $fields.add_field_method_get(stringify!($name), |_lua, this| {
             Ok(Value::$lua_type(this.0.read().$name.into()))
         });
 
         $fields.add_field_method_set(stringify!($name), |_lua, this, v: $type| {
-            this.write().$name = v;
+            let value = $valid(v).context(format!("bad {}: {v}", stringify!($name)))?;
+            this.write().$name = value;
+            Ok(())
+        });
+    };
+}
+
+/// Like [`impl_setget_primitive`], for properties that can be unset
+macro_rules! impl_setget_optional {
+    ($fields: ident, $name: ident, $type: tt, $lua_type: tt) => {
+        impl_setget_optional!($fields, $name, $type, $lua_type, Some);
+    };
+    ($fields: ident, $name: ident, $type: tt, $lua_type: tt, $valid: expr) => {
+        $fields.add_field_method_get(stringify!($name), |_lua, this| {
+            Ok(this
+                .0
+                .read()
+                .$name
+                .map_or(Value::Nil, |v| Value::$lua_type(v.into())))
+        });
+
+        // setting nil clears the property, so it's inherited again
+        $fields.add_field_method_set(stringify!($name), |_lua, this, v: Option<$type>| {
+            let value = v
+                .map(|v| $valid(v).context(format!("bad {}: {v}", stringify!($name))))
+                .transpose()?;
+            this.write().$name = value;
             Ok(())
         });
     };
@@ -133,8 +299,99 @@ macro_rules! impl_setget_color {
             Ok(Value::Nil)
         });
 
-        $fields.add_field_method_set(stringify!($name), |_lua, this, color: String| {
-            this.write().$name = try_parse_color(&color);
+        // setting nil clears the color
+        $fields.add_field_method_set(stringify!($name), |_lua, this, v: Option<String>| {
+            let value = v
+                .map(|v| {
+                    try_parse_color(&v).context(format!("failed to parse {}", stringify!($name)))
+                })
+                .transpose()?;
+            this.write().$name = value;
+            Ok(())
+        });
+    };
+}
+
+macro_rules! impl_setget_edges {
+    ($fields: ident, $name: ident) => {
+        $fields.add_field_method_get(stringify!($name), |lua, this| {
+            Ok(Value::String(
+                lua.create_string(this.0.read().$name.as_text())?,
+            ))
+        });
+
+        $fields.add_field_method_set(stringify!($name), |_lua, this, v: String| {
+            let value =
+                try_parse_edges(&v).context(format!("failed to parse {}", stringify!($name)))?;
+            this.write().$name = value;
+            Ok(())
+        });
+
+        impl_setget_edges!($fields, $name, top);
+        impl_setget_edges!($fields, $name, right);
+        impl_setget_edges!($fields, $name, bottom);
+        impl_setget_edges!($fields, $name, left);
+    };
+    ($fields: ident, $name: ident, $side: ident) => {
+        $fields.add_field_method_get(
+            concat!(stringify!($name), "_", stringify!($side)),
+            |lua, this| {
+                Ok(Value::String(
+                    lua.create_string(this.0.read().$name.$side.as_text())?,
+                ))
+            },
+        );
+
+        $fields.add_field_method_set(
+            concat!(stringify!($name), "_", stringify!($side)),
+            |_lua, this, v: String| {
+                let value = try_parse_dimension(&v).context(format!(
+                    "failed to parse {}_{}",
+                    stringify!($name),
+                    stringify!($side)
+                ))?;
+                this.write().$name.$side = value;
+                Ok(())
+            },
+        );
+    };
+}
+
+macro_rules! impl_setget_corners {
+    ($fields: ident, $name: ident) => {
+        $fields.add_field_method_get(stringify!($name), |lua, this| {
+            Ok(Value::String(
+                lua.create_string(this.0.read().$name.as_text())?,
+            ))
+        });
+
+        $fields.add_field_method_set(stringify!($name), |_lua, this, v: String| {
+            let value =
+                try_parse_corners(&v).context(format!("failed to parse {}", stringify!($name)))?;
+            this.write().$name = value;
+            Ok(())
+        });
+
+        // a corner is set like a side of the edges
+        impl_setget_edges!($fields, $name, top_left);
+        impl_setget_edges!($fields, $name, top_right);
+        impl_setget_edges!($fields, $name, bottom_right);
+        impl_setget_edges!($fields, $name, bottom_left);
+    };
+}
+
+/// For properties that are read as text and parsed back with `$parse`
+macro_rules! impl_setget_text {
+    ($fields: ident, $name: ident, $parse: ident) => {
+        $fields.add_field_method_get(stringify!($name), |lua, this| {
+            Ok(Value::String(
+                lua.create_string(this.0.read().$name.as_text())?,
+            ))
+        });
+
+        $fields.add_field_method_set(stringify!($name), |_lua, this, v: String| {
+            let value = $parse(&v).context(format!("failed to parse {}", stringify!($name)))?;
+            this.write().$name = value;
             Ok(())
         });
     };
@@ -142,13 +399,115 @@ macro_rules! impl_setget_color {
 
 impl UserData for StylingHandle {
     fn add_fields<F: mlua::UserDataFields<Self>>(fields: &mut F) {
-        impl_setget_primitive!(fields, font_size, u16, Number);
+        impl_setget_optional!(fields, font_size, u16, Number);
+        impl_setget_text!(fields, font_family, try_parse_font_family);
         impl_setget_dimension!(fields, width);
         impl_setget_dimension!(fields, height);
+        impl_setget_dimension!(fields, min_width);
+        impl_setget_dimension!(fields, max_width);
+        impl_setget_dimension!(fields, min_height);
+        impl_setget_dimension!(fields, max_height);
+        impl_setget_optional!(fields, aspect_ratio, f32, Number, valid_ratio);
+        impl_setget_enum!(fields, object_fit, COObjectFit);
         impl_setget_enum!(fields, align, COAlignItems);
         impl_setget_enum!(fields, justify, COJustifyContent);
         impl_setget_enum!(fields, flexdir, COFlexDirection);
+        impl_setget_enum!(fields, flex_wrap, COFlexWrap);
+        impl_setget_enum!(fields, align_self, COAlignSelf);
+        impl_setget_enum!(fields, align_content, COAlignContent);
+        impl_setget_primitive!(fields, flex_grow, f32, Number, valid_flex_factor);
+        impl_setget_optional!(fields, flex_shrink, f32, Number, valid_flex_factor);
+        impl_setget_dimension!(fields, flex_basis);
         impl_setget_color!(fields, color);
         impl_setget_color!(fields, background_color);
+        impl_setget_edges!(fields, margin);
+        impl_setget_edges!(fields, padding);
+        impl_setget_edges!(fields, border_width);
+        impl_setget_color!(fields, border_color);
+        impl_setget_enum!(fields, border_style, COBorderStyle);
+        impl_setget_corners!(fields, border_radius);
+        impl_setget_text!(fields, box_shadow, try_parse_box_shadow);
+        impl_setget_enum!(fields, position, COPosition);
+        impl_setget_dimension!(fields, top);
+        impl_setget_dimension!(fields, right);
+        impl_setget_dimension!(fields, bottom);
+        impl_setget_dimension!(fields, left);
+        impl_setget_optional!(fields, z_index, i32, Integer);
+        impl_setget_enum!(fields, overflow, COOverflow);
+        impl_setget_enum!(fields, display, CODisplay);
+        impl_setget_enum!(fields, visibility, COVisibility);
+        impl_setget_text!(fields, grid_template_columns, try_parse_grid_tracks);
+        impl_setget_text!(fields, grid_template_rows, try_parse_grid_tracks);
+        impl_setget_dimension!(fields, row_gap);
+        impl_setget_dimension!(fields, column_gap);
+        impl_setget_text!(fields, grid_column, try_parse_grid_placement);
+        impl_setget_text!(fields, grid_row, try_parse_grid_placement);
+        impl_setget_text!(fields, transform, try_parse_transform);
+        impl_setget_text!(fields, transform_origin, try_parse_transform_origin);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::RwLock;
+
+    use super::*;
+
+    #[test]
+    fn scripts_cant_set_bad_ratios() {
+        let lua = mlua::Lua::new();
+        let style = StylingHandle(RwLock::new(Styling::default()).into());
+        lua.globals().set("style", style.clone()).unwrap();
+
+        for bad in ["0", "-1", "0/0", "1/0"] {
+            let result = lua.load(format!("style.aspect_ratio = {bad}")).exec();
+            assert!(result.is_err(), "{bad} was taken");
+        }
+        assert_eq!(style.0.read().aspect_ratio, None);
+
+        lua.load("style.aspect_ratio = 16/9").exec().unwrap();
+        assert_eq!(style.0.read().aspect_ratio, Some(16.0 / 9.0));
+
+        lua.load("style.aspect_ratio = nil").exec().unwrap();
+        assert_eq!(style.0.read().aspect_ratio, None);
+    }
+
+    #[test]
+    fn scripts_cant_set_bad_values() {
+        let lua = mlua::Lua::new();
+        let style = StylingHandle(RwLock::new(Styling::default()).into());
+        lua.globals().set("style", style.clone()).unwrap();
+
+        for bad in [
+            "style.flex_grow = -1",
+            "style.flex_grow = 0/0",
+            "style.flex_shrink = -1",
+            "style.width = 'wide'",
+            "style.padding_top = 'wide'",
+            "style.background_color = 'reddish'",
+        ] {
+            assert!(lua.load(bad).exec().is_err(), "{bad} was taken");
+        }
+        {
+            let style = style.0.read();
+            assert_eq!(style.flex_grow, 0.0);
+            assert_eq!(style.flex_shrink, None);
+            assert_eq!(style.width, None);
+            assert_eq!(style.padding, Styling::default().padding);
+            assert_eq!(style.background_color, None);
+        }
+
+        lua.load("style.flex_grow = 2; style.width = '50%'; style.color = 'red'")
+            .exec()
+            .unwrap();
+        assert_eq!(style.0.read().flex_grow, 2.0);
+        assert_eq!(style.0.read().width, Some(CODimension::Percent(0.5)));
+        assert!(style.0.read().color.is_some());
+
+        lua.load("style.width = nil; style.color = nil")
+            .exec()
+            .unwrap();
+        assert_eq!(style.0.read().width, None);
+        assert_eq!(style.0.read().color, None);
     }
 }
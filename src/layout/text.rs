@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use crate::layout::{
+    capsule::color::COColor,
+    font::{FontFace, FontSynthesis},
+};
+
+/// How far apart consecutive lines of text are placed
+#[must_use]
+pub const fn line_height(font_size: f32) -> f32 {
+    font_size
+}
+
+/// How thick underlines and strikethroughs are
+#[must_use]
+pub fn decoration_thickness(font_size: f32) -> f32 {
+    (font_size / 16.0).round().max(1.0)
+}
+
+/// What a run of text is drawn with, resolved against the computed style
+/// of the text it's in
+#[derive(Clone, PartialEq, Debug)]
+pub struct TextStyle {
+    /// `None` for the default font
+    pub font: Option<Arc<FontFace>>,
+    pub font_size: f32,
+    pub color: COColor,
+    pub synthesis: FontSynthesis,
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+/// The part of a run that ended up on a line
+#[derive(Clone, PartialEq, Debug)]
+pub struct LineFragment {
+    pub text: String,
+    pub style: TextStyle,
+    /// from the start of the line
+    pub x: f32,
+    /// from the top of the line to the top of the tallest glyph, where
+    /// renderers put the top of text, so the line's fragments share a baseline
+    pub y: f32,
+    pub width: f32,
+}
+
+/// A line of wrapped text
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TextLine {
+    /// left to right, empty for blank lines
+    pub fragments: Vec<LineFragment>,
+    pub width: f32,
+    /// from the top of the line to the top of the next one
+    pub height: f32,
+    /// from the top of the line to the baseline of its fragments
+    pub baseline: f32,
+}
+
+impl TextLine {
+    /// The text of the line without its styles
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.fragments
+            .iter()
+            .map(|fragment| fragment.text.as_str())
+            .collect()
+    }
+}
+
+/// Tells layout how wide text is, so it doesn't depend on a particular
+/// font renderer being available
+pub trait TextMeasurer {
+    /// Width of a single line of text, `font` being `None` for the default
+    /// font
+    fn line_width(&self, text: &str, font: Option<&Arc<FontFace>>, font_size: f32) -> f32;
+
+    /// How far the tallest glyph of `text` reaches above the baseline,
+    /// renderers put the top of that glyph where they're asked to draw text
+    fn ascent(&self, text: &str, font: Option<&Arc<FontFace>>, font_size: f32) -> f32;
+}
+
+/// Gives every character the same width, so layout can run without a
+/// window and always comes out the same. Every font is measured alike, and
+/// every glyph reaches three quarters of the font size above the baseline.
+#[derive(Copy, Clone, Debug)]
+pub struct FixedAdvanceMeasurer {
+    /// width of a character, relative to the font size
+    pub advance: f32,
+}
+
+impl Default for FixedAdvanceMeasurer {
+    fn default() -> Self {
+        Self { advance: 0.5 }
+    }
+}
+
+impl TextMeasurer for FixedAdvanceMeasurer {
+    fn line_width(&self, text: &str, _font: Option<&Arc<FontFace>>, font_size: f32) -> f32 {
+        #[allow(clippy::cast_precision_loss)]
+        let chars = text.chars().count() as f32;
+
+        chars * self.advance * font_size
+    }
+
+    fn ascent(&self, _text: &str, _font: Option<&Arc<FontFace>>, font_size: f32) -> f32 {
+        font_size * 0.75
+    }
+}
+
+/// Adds `text` of the run `run` to the end of `line`, as part of its last
+/// fragment when that's of the same run
+fn push_fragment(line: &mut Vec<(usize, String)>, run: usize, text: &str) {
+    match line.last_mut() {
+        Some((last, fragment)) if *last == run => fragment.push_str(text),
+        _ => line.push((run, text.to_owned())),
+    }
+}
+
+/// Breaks text made of several runs into lines, like [`wrap_text`]. Words
+/// can span runs, only whitespace separates them. Lines are made of the
+/// fragments of runs on them, with the index of their run, and `measure`
+/// is given the index of the run of the text it measures.
+#[must_use]
+pub fn wrap_runs<F>(runs: &[&str], max_width: Option<f32>, measure: F) -> Vec<Vec<(usize, String)>>
+where
+    F: Fn(usize, &str) -> f32,
+{
+    // layout and rendering wrap the same text at widths that can differ by
+    // float rounding, so this keeps them from disagreeing on a line break
+    const TOLERANCE: f32 = 0.5;
+
+    // the words of every paragraph, with the run of the space before them
+    let mut paragraphs = vec![Vec::new()];
+    let mut in_word = false;
+    let mut space = None;
+
+    for (run, text) in runs.iter().enumerate() {
+        for c in text.chars() {
+            if c == '\n' {
+                paragraphs.push(Vec::new());
+                in_word = false;
+                space = None;
+            } else if c.is_whitespace() {
+                in_word = false;
+                space = space.or(Some(run));
+            } else {
+                let words = paragraphs.last_mut().unwrap();
+
+                if !in_word {
+                    words.push((space.take().unwrap_or(run), Vec::new()));
+                    in_word = true;
+                }
+
+                let (_, word) = words.last_mut().unwrap();
+                push_fragment(word, run, c.encode_utf8(&mut [0; 4]));
+            }
+        }
+    }
+
+    let width = |line: &[(usize, String)]| -> f32 {
+        line.iter().map(|(run, text)| measure(*run, text)).sum()
+    };
+    let mut lines = Vec::new();
+
+    for words in paragraphs {
+        let mut line = Vec::new();
+
+        for (space, word) in words {
+            if line.is_empty() {
+                line = word;
+                continue;
+            }
+
+            let mut candidate = line.clone();
+            push_fragment(&mut candidate, space, " ");
+            for (run, text) in &word {
+                push_fragment(&mut candidate, *run, text);
+            }
+
+            if max_width.is_some_and(|max| width(&candidate) > max + TOLERANCE) {
+                lines.push(std::mem::replace(&mut line, word));
+            } else {
+                line = candidate;
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Breaks `text` into lines. Explicit newlines are always honored, and when
+/// `max_width` is given words are moved onto the next line instead of
+/// overflowing it. A single word wider than `max_width` gets a line of its own.
+#[must_use]
+pub fn wrap_text<F>(text: &str, max_width: Option<f32>, measure: F) -> Vec<String>
+where
+    F: Fn(&str) -> f32,
+{
+    wrap_runs(&[text], max_width, |_, text| measure(text))
+        .into_iter()
+        .map(|line| line.into_iter().map(|(_, text)| text).collect())
+        .collect()
+}
+
+/// Wraps styled runs of text into lines and places their fragments. Lines
+/// are at least `min_line_height` tall, the line height of the text's own
+/// font size, and as tall as their largest fragment needs.
+#[must_use]
+pub fn lay_out_text(
+    runs: &[(String, TextStyle)],
+    max_width: Option<f32>,
+    min_line_height: f32,
+    measurer: &dyn TextMeasurer,
+) -> Vec<TextLine> {
+    let measure = |run: usize, text: &str| {
+        let style = &runs[run].1;
+
+        measurer.line_width(text, style.font.as_ref(), style.font_size)
+            + style.synthesis.bold_offset(style.font_size)
+    };
+    let texts: Vec<_> = runs.iter().map(|(text, _)| text.as_str()).collect();
+
+    wrap_runs(&texts, max_width, measure)
+        .into_iter()
+        .map(|fragments| {
+            let ascents: Vec<_> = fragments
+                .iter()
+                .map(|(run, text)| {
+                    let style = &runs[*run].1;
+                    measurer.ascent(text, style.font.as_ref(), style.font_size)
+                })
+                .collect();
+            let baseline = ascents.iter().copied().fold(0.0, f32::max);
+            let mut line = TextLine {
+                baseline,
+                height: min_line_height,
+                ..TextLine::default()
+            };
+
+            for ((run, text), ascent) in fragments.into_iter().zip(ascents) {
+                let style = &runs[run].1;
+                let width = measure(run, &text);
+
+                line.height = line.height.max(line_height(style.font_size));
+                line.fragments.push(LineFragment {
+                    text,
+                    style: style.clone(),
+                    x: line.width,
+                    y: baseline - ascent,
+                    width,
+                });
+                line.width += width;
+            }
+
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(clippy::cast_precision_loss)]
+    fn monospace(text: &str) -> f32 {
+        text.chars().count() as f32 * 10.0
+    }
+
+    #[test]
+    fn words_wrap_to_the_width() {
+        assert_eq!(
+            wrap_text("the quick brown fox", Some(100.0), monospace),
+            ["the quick", "brown fox"]
+        );
+        assert_eq!(
+            wrap_text("the quick brown fox", None, monospace),
+            ["the quick brown fox"]
+        );
+    }
+
+    #[test]
+    fn newlines_and_long_words() {
+        assert_eq!(
+            wrap_text("a\n\nincomprehensibilities b", Some(50.0), monospace),
+            ["a", "", "incomprehensibilities", "b"]
+        );
+    }
+
+    #[test]
+    fn words_span_runs() {
+        let lines = wrap_runs(&["Price: $", "10", " each"], Some(100.0), |_, text| {
+            monospace(text)
+        });
+
+        assert_eq!(
+            lines,
+            [
+                vec![(0, "Price: $".to_owned()), (1, "10".to_owned())],
+                vec![(2, "each".to_owned())]
+            ]
+        );
+    }
+
+    #[test]
+    fn fragments_share_a_baseline() {
+        let style = |font_size, bold| TextStyle {
+            font: None,
+            font_size,
+            color: COColor::default(),
+            synthesis: FontSynthesis {
+                bold,
+                italic: false,
+            },
+            underline: false,
+            strikethrough: false,
+        };
+        let runs = [
+            ("big ".to_owned(), style(20.0, false)),
+            ("small".to_owned(), style(12.0, true)),
+        ];
+        let lines = lay_out_text(&runs, None, 16.0, &FixedAdvanceMeasurer::default());
+        let [line] = &lines[..] else {
+            panic!("{lines:?}");
+        };
+
+        assert_eq!((line.height, line.baseline), (20.0, 15.0));
+        assert_eq!(line.fragments[0].y, 0.0);
+        assert_eq!(line.fragments[1].y, 15.0 - 9.0);
+        // bold text is a pixel wider
+        assert_eq!(line.fragments[1].x, 40.0);
+        assert_eq!(line.width, 40.0 + 30.0 + 1.0);
+    }
+}
@@ -1,9 +1,14 @@
 use std::sync::Arc;
 
 use crate::{
-    capsule::{obj::CapsuleObject, objs::text::CSText},
+    capsule::{
+        obj::CapsuleObject,
+        objs::{image::CSImage, text::CSText},
+    },
     layout::styling::StylingHandle,
+    renderer::backend::Renderer,
 };
+use macroquad::math::Vec2;
 use mlua::{UserData, Value};
 
 #[derive(Debug, Clone)]
@@ -15,7 +20,7 @@ impl UserData for CapsuleObjectHandle {
 
         fields.add_field_method_get("text", |lua, this| {
             if let Some(text) = this.0.as_any().downcast_ref::<CSText>() {
-                return Ok(Value::String(lua.create_string(&*text.text.read())?));
+                return Ok(Value::String(lua.create_string(text.text())?));
             }
 
             Ok(Value::Nil)
@@ -29,6 +34,25 @@ impl UserData for CapsuleObjectHandle {
 
             Ok(())
         });
+
+        fields.add_field_method_get("src", |lua, this| {
+            if let Some(image) = this.0.as_any().downcast_ref::<CSImage>() {
+                return Ok(Value::String(lua.create_string(&*image.src.read())?));
+            }
+
+            Ok(Value::Nil)
+        });
+
+        // a missing image isn't the script's fault, its alt text is shown
+        fields.add_field_method_set("src", |_lua, this, v: String| {
+            if let Some(image) = this.0.as_any().downcast_ref::<CSImage>()
+                && let Err(e) = image.set_src(v)
+            {
+                log::warn!("failed to load image: {e:#}");
+            }
+
+            Ok(())
+        });
     }
 }
 
@@ -50,6 +74,24 @@ where
         Ok(Value::Table(table))
     });
 
+    fields.add_field_method_get("scroll_x", |_lua, this: &T| Ok(this.base().scroll.read().x));
+
+    fields.add_field_method_set("scroll_x", |_lua, this: &mut T, v: f32| {
+        let base = this.base();
+        let scroll = *base.scroll.read();
+        base.set_scroll(Vec2::new(v, scroll.y));
+        Ok(())
+    });
+
+    fields.add_field_method_get("scroll_y", |_lua, this: &T| Ok(this.base().scroll.read().y));
+
+    fields.add_field_method_set("scroll_y", |_lua, this: &mut T, v: f32| {
+        let base = this.base();
+        let scroll = *base.scroll.read();
+        base.set_scroll(Vec2::new(scroll.x, v));
+        Ok(())
+    });
+
     fields.add_field_method_get("style", |lua, this: &T| {
         let handle = StylingHandle(this.base().style.clone());
         lua.create_userdata(handle)
@@ -61,8 +103,8 @@ impl CapsuleObject for CapsuleObjectHandle {
         self.0.base()
     }
 
-    fn render(&self) {
-        self.0.render();
+    fn render(&self, renderer: &mut dyn Renderer) {
+        self.0.render(renderer);
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
@@ -16,6 +16,14 @@ fn used_memory(lua: &Lua, _: ()) -> LuaResult<usize> {
     Ok(lua.used_memory())
 }
 
+fn get_viewport(lua: &Lua, capsule: &ArcLock<Capsule>) -> LuaResult<LuaTable> {
+    let viewport = capsule.read().viewport;
+    let table = lua.create_table()?;
+    table.set("width", viewport.x)?;
+    table.set("height", viewport.y)?;
+    Ok(table)
+}
+
 fn get_root(_lua: &Lua, capsule: &ArcLock<Capsule>) -> LuaResult<CSView> {
     Ok(capsule.read().view.clone())
 }
@@ -29,6 +37,11 @@ pub fn get_capsule_module(lua: &Lua, capsule: &ArcLock<Capsule>) -> LuaResult<Lu
         lua.create_function(move |lua: &Lua, (): ()| get_root(lua, &capsule_c))?,
     )?;
     let capsule_c = Arc::clone(capsule);
+    exports.set(
+        "viewport",
+        lua.create_function(move |lua: &Lua, (): ()| get_viewport(lua, &capsule_c))?,
+    )?;
+    let capsule_c = Arc::clone(capsule);
     exports.set(
         "find_element",
         lua.create_function(move |_lua: &Lua, id: String| {
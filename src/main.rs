@@ -8,10 +8,16 @@ use macroquad::prelude::*;
 use parking_lot::RwLock;
 
 use crate::{
-    capsule::{Capsule, obj::iter_all_objects, parser::parse_capsule},
-    event::update::update_events,
-    layout::{computer::compute_layout, dirty::update_layout},
-    renderer::full::render_capsule,
+    capsule::{Capsule, diagnostic::CapsuleDiagnostic, parser::parse_capsule_file},
+    event::update::{dispatch_resize, update_events},
+    layout::capsule::color::COColor,
+    layout::{computer::LayoutTree, stacking::StackingOrder},
+    renderer::{
+        backend::Renderer,
+        software::SoftwareRenderer,
+        text::MacroquadMeasurer,
+        window::{WindowCanvas, WindowRenderer},
+    },
 };
 
 pub mod capsule;
@@ -28,34 +34,81 @@ fn window_conf() -> Conf {
         window_title: "Capsule Browser".into(),
         window_width: WINDOW_WIDTH,
         window_height: WINDOW_HEIGHT,
+        window_resizable: true,
         ..Default::default()
     }
 }
 
-#[macroquad::main(window_conf)]
-async fn main() {
+fn log_diagnostics(diagnostics: &[CapsuleDiagnostic]) {
+    for diagnostic in diagnostics {
+        log::log!(diagnostic.log_level(), "test.capsule:{diagnostic}");
+    }
+}
+
+/// Renders `test.capsule` without a window and writes it to `path` as a PNG
+fn screenshot(path: &str) -> anyhow::Result<()> {
+    let (mut capsule, diagnostics) = parse_capsule_file("test.capsule")?;
+    log_diagnostics(&diagnostics);
+
+    #[allow(clippy::cast_sign_loss)]
+    let mut renderer = SoftwareRenderer::new(
+        WINDOW_WIDTH as u32,
+        WINDOW_HEIGHT as u32,
+        layout::capsule::color::BLACK,
+    );
+    #[allow(clippy::cast_precision_loss)]
+    {
+        capsule.viewport = vec2(WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32);
+    }
+    let mut layout = LayoutTree::new(&capsule, Arc::new(renderer.measurer()));
+
+    let capsule = Arc::new(RwLock::new(capsule));
+    Capsule::run_scripts(&capsule.clone());
+    layout.update();
+
+    layout
+        .display_list_mut()
+        .repaint(&mut renderer, layout::capsule::color::BLACK);
+    renderer.bitmap().write_png(path)
+}
+
+fn main() {
+    env_logger::builder()
+        .filter_level(log::LevelFilter::Trace)
+        .init();
+
+    let mut args = std::env::args().skip(1);
+
+    if args.next().as_deref() == Some("--screenshot") {
+        let path = args.next().unwrap_or_else(|| "screenshot.png".to_owned());
+
+        if let Err(e) = screenshot(&path) {
+            log::error!("failed to take a screenshot: {e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    macroquad::Window::from_config(window_conf(), run());
+}
+
+async fn run() {
     struct DebugView {
         pub show_mouse_hit: bool,
     }
 
-    fn render_debug_view(debug_view: &DebugView, capsule: &Capsule) {
+    fn render_debug_view(debug_view: &DebugView, capsule: &Capsule, renderer: &mut dyn Renderer) {
         if debug_view.show_mouse_hit {
             let mouse_position = Vec2::from(mouse_position());
 
-            iter_all_objects(capsule, |e| {
-                let bb = e.map(|o| o.bounding_box());
-
-                if bb.contains(mouse_position) {
-                    draw_rectangle(bb.x, bb.y, bb.w, bb.h, Color::from_rgba(255, 255, 0, 128));
-                }
-            });
+            if let Some(target) = StackingOrder::new(capsule).hit_path(mouse_position).first() {
+                renderer.set_transform(target.base().computed_style.read().transform);
+                renderer.fill_rect(target.bounding_box(), COColor::from_rgba(255, 255, 0, 128));
+                renderer.set_transform(Affine2::IDENTITY);
+            }
         }
     }
 
-    env_logger::builder()
-        .filter_level(log::LevelFilter::Trace)
-        .init();
-
     {
         use parking_lot::deadlock;
         use std::{thread, time::Duration};
@@ -80,9 +133,12 @@ async fn main() {
         });
     }
 
-    let mut capsule = parse_capsule(&std::fs::read_to_string("test.capsule").unwrap())
-        .expect("failed to parse capsule");
-    compute_layout(&mut capsule);
+    let (mut capsule, diagnostics) =
+        parse_capsule_file("test.capsule").expect("failed to parse capsule");
+    log_diagnostics(&diagnostics);
+    let mut viewport = vec2(screen_width(), screen_height());
+    capsule.viewport = viewport;
+    let mut layout = LayoutTree::new(&capsule, Arc::new(MacroquadMeasurer));
 
     let mut capsule_arc = Arc::new(RwLock::new(capsule));
     Capsule::run_scripts(&capsule_arc.clone());
@@ -90,19 +146,23 @@ async fn main() {
     let mut debug_view = DebugView {
         show_mouse_hit: false,
     };
+    let mut renderer = WindowRenderer::default();
+    let mut canvas = WindowCanvas::default();
 
     loop {
         if is_key_pressed(KeyCode::F5) {
-            match parse_capsule(&std::fs::read_to_string("test.capsule").unwrap()) {
-                Ok(mut cap) => {
-                    compute_layout(&mut cap);
+            match parse_capsule_file("test.capsule") {
+                Ok((mut cap, diagnostics)) => {
+                    log_diagnostics(&diagnostics);
+                    cap.viewport = viewport;
+                    layout = LayoutTree::new(&cap, Arc::new(MacroquadMeasurer));
                     let cap = Arc::new(RwLock::new(cap));
                     capsule_arc = cap;
                     Capsule::run_scripts(&capsule_arc.clone());
                     log::info!("Reloaded!");
                 }
                 Err(e) => {
-                    log::error!("failed to parse capsule: {e:#?}");
+                    log::error!("failed to parse capsule: test.capsule:{e}");
                 }
             }
         }
@@ -111,14 +171,22 @@ async fn main() {
             debug_view.show_mouse_hit = !debug_view.show_mouse_hit;
         }
 
-        update_layout(&capsule_arc.clone());
+        let screen = vec2(screen_width(), screen_height());
+        if screen != viewport {
+            viewport = screen;
+            capsule_arc.write().viewport = viewport;
+            layout.resize(viewport);
+            dispatch_resize(&capsule_arc.clone());
+        }
+
+        layout.update();
         update_events(&capsule_arc.clone());
 
         {
             let cap = capsule_arc.read();
             clear_background(BLACK);
-            render_capsule(&cap);
-            render_debug_view(&debug_view, &cap);
+            canvas.paint(layout.display_list_mut(), layout::capsule::color::BLACK);
+            render_debug_view(&debug_view, &cap, &mut renderer);
         }
 
         next_frame().await;
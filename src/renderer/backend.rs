@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use macroquad::math::{Affine2, Rect, Vec2};
+
+use crate::{
+    layout::{
+        capsule::color::COColor,
+        font::{FontFace, FontSynthesis},
+    },
+    renderer::bitmap::Bitmap,
+};
+
+/// Something capsules can be drawn with. Coordinates are relative to the
+/// window, go through the current transform and are then clipped to the
+/// current clip rect.
+pub trait Renderer {
+    /// Transforms everything drawn after it
+    fn set_transform(&mut self, transform: Affine2);
+
+    /// Restricts drawing to `clip`, in window coordinates that aren't
+    /// transformed, or lifts the restriction when it's `None`
+    fn set_clip(&mut self, clip: Option<Rect>);
+
+    fn fill_rect(&mut self, rect: Rect, color: COColor);
+
+    /// Fills a convex polygon whose points go clockwise on the window, i.e.
+    /// right along its top edge
+    fn fill_polygon(&mut self, points: &[Vec2], color: COColor);
+
+    /// Draws the `source` part of `image`, in its pixels, stretched over
+    /// `destination`
+    fn draw_image(&mut self, image: &Arc<Bitmap>, source: Rect, destination: Rect);
+
+    /// Draws a single line of text with its top left corner at `position`,
+    /// `font` being `None` for the default font. The top is where its
+    /// tallest glyph starts, see [`TextMeasurer::ascent`].
+    ///
+    /// [`TextMeasurer::ascent`]: crate::layout::text::TextMeasurer::ascent
+    fn draw_text(
+        &mut self,
+        text: &str,
+        position: Vec2,
+        font: Option<&Arc<FontFace>>,
+        synthesis: FontSynthesis,
+        font_size: f32,
+        color: COColor,
+    );
+}
@@ -0,0 +1,161 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use anyhow::{Context, bail};
+
+/// An 8 bit RGBA image, row by row
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Bitmap {
+    /// An image filled with `color`
+    #[must_use]
+    pub fn filled(width: u32, height: u32, color: [u8; 4]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: color.repeat((width * height) as usize),
+        }
+    }
+
+    const fn index(&self, x: u32, y: u32) -> usize {
+        ((y * self.width + x) * 4) as usize
+    }
+
+    #[must_use]
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = self.index(x, y);
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    pub fn pixel_mut(&mut self, x: u32, y: u32) -> &mut [u8] {
+        let i = self.index(x, y);
+        &mut self.pixels[i..i + 4]
+    }
+
+    /// Decodes a PNG of any color type into RGBA
+    pub fn decode_png(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let buffer = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer.to_vec(),
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            png::ColorType::Grayscale => {
+                buffer.iter().flat_map(|g| [*g, *g, *g, u8::MAX]).collect()
+            }
+            png::ColorType::Indexed => bail!("palette wasn't expanded"),
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// Decodes a baseline or progressive JPEG into RGBA
+    pub fn decode_jpeg(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut decoder = jpeg_decoder::Decoder::new(bytes);
+        let buffer = decoder.decode()?;
+        let info = decoder.info().context("JPEG has no frame")?;
+
+        let pixels = match info.pixel_format {
+            jpeg_decoder::PixelFormat::RGB24 => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+                .collect(),
+            jpeg_decoder::PixelFormat::L8 => {
+                buffer.iter().flat_map(|g| [*g, *g, *g, u8::MAX]).collect()
+            }
+            jpeg_decoder::PixelFormat::L16 => buffer
+                .chunks_exact(2)
+                .flat_map(|g| {
+                    let [g, _] = u16::from_ne_bytes([g[0], g[1]]).to_be_bytes();
+                    [g, g, g, u8::MAX]
+                })
+                .collect(),
+            jpeg_decoder::PixelFormat::CMYK32 => buffer
+                .chunks_exact(4)
+                .flat_map(|cmyk| {
+                    // every channel is how much ink there is
+                    let white = u16::from(u8::MAX - cmyk[3]);
+                    #[allow(clippy::cast_possible_truncation)]
+                    let channel = |ink: u8| (u16::from(u8::MAX - ink) * white / 255) as u8;
+                    [
+                        channel(cmyk[0]),
+                        channel(cmyk[1]),
+                        channel(cmyk[2]),
+                        u8::MAX,
+                    ]
+                })
+                .collect(),
+        };
+
+        Ok(Self {
+            width: u32::from(info.width),
+            height: u32::from(info.height),
+            pixels,
+        })
+    }
+
+    /// Decodes a PNG or a JPEG, told apart by their signatures
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Self::decode_png(bytes)
+        } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+            Self::decode_jpeg(bytes)
+        } else {
+            bail!("not a PNG or a JPEG")
+        }
+    }
+
+    /// Reads a PNG or a JPEG
+    pub fn read(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let bytes =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+
+        Self::decode(&bytes).with_context(|| format!("failed to decode {}", path.display()))
+    }
+
+    pub fn read_png(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let bytes =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+
+        Self::decode_png(&bytes).with_context(|| format!("failed to decode {}", path.display()))
+    }
+
+    pub fn write_png(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+
+        Ok(())
+    }
+}
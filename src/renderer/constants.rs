@@ -1,2 +1,4 @@
 pub const DEFAULT_TEXT_SIZE: u16 = 24;
 pub const BR_LINE_HEIGHT: f32 = 16.0;
+/// The font macroquad draws text with, so headless renders look like the window
+pub const DEFAULT_FONT: &[u8] = include_bytes!("../../assets/ProggyClean.ttf");
@@ -0,0 +1,475 @@
+use std::{collections::HashMap, sync::Arc};
+
+use macroquad::math::{Affine2, Rect, Vec2};
+
+use crate::{
+    layout::{
+        capsule::color::COColor,
+        font::{FontFace, FontSynthesis},
+        text::{TextMeasurer, line_height},
+    },
+    renderer::{backend::Renderer, bitmap::Bitmap},
+};
+
+/// Past this many damaged rects, their union is repainted instead
+const MAX_DAMAGE_RECTS: usize = 16;
+
+/// A call to a [`Renderer`], kept to be replayed later
+#[derive(Clone, PartialEq, Debug)]
+pub enum DrawCommand {
+    SetTransform(Affine2),
+    SetClip(Option<Rect>),
+    FillRect(Rect, COColor),
+    FillPolygon(Vec<Vec2>, COColor),
+    DrawImage {
+        image: Arc<Bitmap>,
+        source: Rect,
+        destination: Rect,
+    },
+    DrawText {
+        text: String,
+        position: Vec2,
+        font: Option<Arc<FontFace>>,
+        synthesis: FontSynthesis,
+        font_size: f32,
+        color: COColor,
+    },
+}
+
+/// What one object draws
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct DisplayItem {
+    pub commands: Vec<DrawCommand>,
+    /// the part of the window the commands can touch, in whole pixels
+    pub bounds: Option<Rect>,
+}
+
+/// Smallest rect holding both
+fn union(a: Rect, b: Rect) -> Rect {
+    let min = a.point().min(b.point());
+    let max = Vec2::new(a.right(), a.bottom()).max(Vec2::new(b.right(), b.bottom()));
+
+    Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+}
+
+/// Grows `rect` to the pixels it touches
+fn round_out(rect: Rect) -> Rect {
+    let min = rect.point().floor();
+    let max = Vec2::new(rect.right(), rect.bottom()).ceil();
+
+    Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)
+}
+
+fn reset(renderer: &mut dyn Renderer) {
+    renderer.set_clip(None);
+    renderer.set_transform(Affine2::IDENTITY);
+}
+
+/// Records what is drawn with it into a [`DisplayItem`] instead of drawing it
+pub struct Recorder {
+    item: DisplayItem,
+    transform: Affine2,
+    clip: Option<Rect>,
+    measurer: Arc<dyn TextMeasurer>,
+}
+
+impl Recorder {
+    /// `measurer` has to measure text like the renderers the item is
+    /// replayed with, or the item's bounds will be off
+    #[must_use]
+    pub fn new(measurer: Arc<dyn TextMeasurer>) -> Self {
+        Self {
+            item: DisplayItem::default(),
+            transform: Affine2::IDENTITY,
+            clip: None,
+            measurer,
+        }
+    }
+
+    #[must_use]
+    pub fn finish(self) -> DisplayItem {
+        self.item
+    }
+
+    /// Adds `rect`, before being transformed and clipped, to the bounds
+    fn cover(&mut self, rect: Rect) {
+        let corners = [
+            rect.point(),
+            Vec2::new(rect.right(), rect.y),
+            Vec2::new(rect.x, rect.bottom()),
+            Vec2::new(rect.right(), rect.bottom()),
+        ]
+        .map(|corner| self.transform.transform_point2(corner));
+        let min = corners.into_iter().reduce(Vec2::min).unwrap();
+        let max = corners.into_iter().reduce(Vec2::max).unwrap();
+        let mut covered = Rect::new(min.x, min.y, max.x - min.x, max.y - min.y);
+
+        if let Some(clip) = self.clip {
+            let Some(clipped) = covered.intersect(clip) else {
+                return;
+            };
+            covered = clipped;
+        }
+
+        let covered = round_out(covered);
+        self.item.bounds = Some(
+            self.item
+                .bounds
+                .map_or(covered, |bounds| union(bounds, covered)),
+        );
+    }
+}
+
+impl Renderer for Recorder {
+    fn set_transform(&mut self, transform: Affine2) {
+        self.transform = transform;
+        self.item
+            .commands
+            .push(DrawCommand::SetTransform(transform));
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip;
+        self.item.commands.push(DrawCommand::SetClip(clip));
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: COColor) {
+        self.cover(rect);
+        self.item.commands.push(DrawCommand::FillRect(rect, color));
+    }
+
+    fn fill_polygon(&mut self, points: &[Vec2], color: COColor) {
+        if let (Some(min), Some(max)) = (
+            points.iter().copied().reduce(Vec2::min),
+            points.iter().copied().reduce(Vec2::max),
+        ) {
+            self.cover(Rect::new(min.x, min.y, max.x - min.x, max.y - min.y));
+        }
+
+        self.item
+            .commands
+            .push(DrawCommand::FillPolygon(points.to_vec(), color));
+    }
+
+    fn draw_image(&mut self, image: &Arc<Bitmap>, source: Rect, destination: Rect) {
+        self.cover(destination);
+        self.item.commands.push(DrawCommand::DrawImage {
+            image: image.clone(),
+            source,
+            destination,
+        });
+    }
+
+    fn draw_text(
+        &mut self,
+        text: &str,
+        position: Vec2,
+        font: Option<&Arc<FontFace>>,
+        synthesis: FontSynthesis,
+        font_size: f32,
+        color: COColor,
+    ) {
+        // glyphs can reach a pixel past their advance and line
+        let width =
+            self.measurer.line_width(text, font, font_size) + synthesis.bold_offset(font_size);
+        let lean = synthesis.italic_lean(font_size);
+        self.cover(Rect::new(
+            position.x - lean - 1.0,
+            position.y - 1.0,
+            width + lean * 2.0 + 2.0,
+            line_height(font_size) + 2.0,
+        ));
+        self.item.commands.push(DrawCommand::DrawText {
+            text: text.to_owned(),
+            position,
+            font: font.cloned(),
+            synthesis,
+            font_size,
+            color,
+        });
+    }
+}
+
+/// The items of `new` that aren't part of the longest run of items keeping
+/// the order they had in `old`, the fewest that have to be moved to turn one
+/// into the other. Items that weren't in `old` count as moved.
+fn moved_items<'a>(old: &[usize], new: &'a [usize]) -> impl Iterator<Item = usize> + 'a {
+    let mut old_positions = HashMap::new();
+    for (position, index) in old.iter().enumerate() {
+        old_positions.insert(*index, position);
+    }
+
+    // longest increasing run of old positions: `tails[n]` is the position in
+    // `new` of the item ending the best run of length n + 1 found so far
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; new.len()];
+
+    for (position, index) in new.iter().enumerate() {
+        let Some(old_position) = old_positions.get(index) else {
+            continue;
+        };
+        let length = tails.partition_point(|tail| old_positions[&new[*tail]] < *old_position);
+
+        previous[position] = length.checked_sub(1).map(|n| tails[n]);
+        if length == tails.len() {
+            tails.push(position);
+        } else {
+            tails[length] = position;
+        }
+    }
+
+    let mut kept = vec![false; new.len()];
+    let mut next = tails.last().copied();
+    while let Some(position) = next {
+        kept[position] = true;
+        next = previous[position];
+    }
+
+    new.iter()
+        .zip(kept)
+        .filter(|(_, kept)| !kept)
+        .map(|(index, _)| *index)
+}
+
+/// The draw commands of every visible object in paint order, kept from
+/// frame to frame. Items are replaced as objects change, and the parts of the
+/// window they covered before and after are collected as damage, so a
+/// renderer that keeps what it drew only has to repaint those.
+#[derive(Default, Debug)]
+pub struct DisplayList {
+    /// indexed like the layout tree's entries
+    items: Vec<Option<DisplayItem>>,
+    /// indices into `items`, back to front
+    order: Vec<usize>,
+    damage: Vec<Rect>,
+}
+
+impl DisplayList {
+    /// Replaces the item at `index`, `None` removes it
+    pub fn set_item(&mut self, index: usize, item: Option<DisplayItem>) {
+        if index >= self.items.len() {
+            self.items.resize(index + 1, None);
+        }
+
+        let old = std::mem::replace(&mut self.items[index], item);
+
+        if old != self.items[index] {
+            let new = self.items[index].as_ref().and_then(|item| item.bounds);
+
+            for bounds in [old.and_then(|item| item.bounds), new]
+                .into_iter()
+                .flatten()
+            {
+                self.damage(bounds);
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn item(&self, index: usize) -> Option<&DisplayItem> {
+        self.items.get(index).and_then(Option::as_ref)
+    }
+
+    /// Indices of the items in the order they are painted in, back to front
+    #[must_use]
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Sets the order the items are painted in, back to front. The items
+    /// that moved relative to the others are damaged, as what overlaps them
+    /// may now be painted the other way around.
+    pub fn set_order(&mut self, order: Vec<usize>) {
+        if order == self.order {
+            return;
+        }
+
+        let old = std::mem::replace(&mut self.order, order);
+        let moved: Vec<_> = moved_items(&old, &self.order)
+            .filter_map(|index| self.item(index).and_then(|item| item.bounds))
+            .collect();
+
+        for bounds in moved {
+            self.damage(bounds);
+        }
+    }
+
+    /// Marks part of the window to be repainted
+    pub fn damage(&mut self, rect: Rect) {
+        if rect.w <= 0.0 || rect.h <= 0.0 {
+            return;
+        }
+
+        self.damage.push(rect);
+
+        if self.damage.len() > MAX_DAMAGE_RECTS {
+            let all = self.damage.drain(..).reduce(union).unwrap();
+            self.damage.push(all);
+        }
+    }
+
+    /// Whether anything has to be repainted
+    #[must_use]
+    pub fn is_damaged(&self) -> bool {
+        !self.damage.is_empty()
+    }
+
+    /// Draws every item
+    pub fn replay(&self, renderer: &mut dyn Renderer) {
+        self.replay_in(renderer, None);
+        reset(renderer);
+    }
+
+    /// Paints `background` over the damaged parts of the window and draws
+    /// the items that touch them again, clipped to them
+    pub fn repaint(&mut self, renderer: &mut dyn Renderer, background: COColor) {
+        for region in std::mem::take(&mut self.damage) {
+            renderer.set_transform(Affine2::IDENTITY);
+            renderer.set_clip(Some(region));
+            renderer.fill_rect(region, background);
+            self.replay_in(renderer, Some(region));
+        }
+
+        reset(renderer);
+    }
+
+    fn replay_in(&self, renderer: &mut dyn Renderer, region: Option<Rect>) {
+        for item in self.order.iter().filter_map(|index| self.item(*index)) {
+            let Some(bounds) = item.bounds else {
+                continue;
+            };
+
+            if region.is_some_and(|region| region.intersect(bounds).is_none()) {
+                continue;
+            }
+
+            for command in &item.commands {
+                match command {
+                    DrawCommand::SetTransform(transform) => renderer.set_transform(*transform),
+                    DrawCommand::SetClip(clip) => {
+                        let clip = match (clip, region) {
+                            (Some(clip), Some(region)) => Some(
+                                clip.intersect(region)
+                                    .unwrap_or(Rect::new(region.x, region.y, 0.0, 0.0)),
+                            ),
+                            (clip, region) => clip.or(region),
+                        };
+
+                        renderer.set_clip(clip);
+                    }
+                    DrawCommand::FillRect(rect, color) => renderer.fill_rect(*rect, *color),
+                    DrawCommand::FillPolygon(points, color) => {
+                        renderer.fill_polygon(points, *color);
+                    }
+                    DrawCommand::DrawImage {
+                        image,
+                        source,
+                        destination,
+                    } => renderer.draw_image(image, *source, *destination),
+                    DrawCommand::DrawText {
+                        text,
+                        position,
+                        font,
+                        synthesis,
+                        font_size,
+                        color,
+                    } => renderer.draw_text(
+                        text,
+                        *position,
+                        font.as_ref(),
+                        *synthesis,
+                        *font_size,
+                        *color,
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        capsule::{obj::CapsuleObject, parser::parse_capsule},
+        layout::{capsule::color::RED, computer::LayoutTree, text::FixedAdvanceMeasurer},
+    };
+
+    #[test]
+    fn only_what_changed_is_damaged() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>damage</title>
+    </meta>
+    <view flexdir="column">
+        <obj width="100" height="20" background_color="green" />
+        <obj width="50.5" height="10" background_color="blue" />
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let mut tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+
+        assert!(
+            tree.display_list()
+                .damage
+                .contains(&Rect::new(0.0, 0.0, 800.0, 600.0))
+        );
+        assert_eq!(tree.display_list().order().len(), 3);
+        tree.display_list_mut().damage.clear();
+
+        tree.update();
+        assert!(!tree.display_list().is_damaged());
+
+        let second = capsule.view.base().children_vec()[1].clone();
+        {
+            let base = second.base();
+            let mut style = base.style.write();
+            style.background_color = Some(RED);
+            style.set_dirty();
+        }
+        tree.update();
+
+        // before and after, rounded out to whole pixels
+        let bounds = Rect::new(0.0, 20.0, 51.0, 10.0);
+        assert_eq!(tree.display_list().damage, [bounds, bounds]);
+    }
+
+    #[test]
+    fn restacking_damages_what_moved() {
+        let (mut capsule, _) = parse_capsule(
+            r#"<capsule>
+    <meta>
+        <title>restack</title>
+    </meta>
+    <view>
+        <obj position="absolute" left="0" top="0" width="40" height="40" background_color="green" />
+        <obj position="absolute" left="20" top="20" width="40" height="40" background_color="blue" />
+        <obj position="absolute" left="100" top="0" width="10" height="10" background_color="red" />
+    </view>
+</capsule>"#,
+        )
+        .unwrap();
+        capsule.viewport = Vec2::new(800.0, 600.0);
+        let mut tree = LayoutTree::new(&capsule, Arc::new(FixedAdvanceMeasurer::default()));
+        tree.display_list_mut().damage.clear();
+
+        let first = capsule.view.base().children_vec()[0].clone();
+        {
+            let base = first.base();
+            let mut style = base.style.write();
+            style.z_index = Some(1);
+            style.set_dirty();
+        }
+        tree.update();
+
+        // the green square is painted over the blue one now, which only
+        // changes the pixels it covers. The others kept their order.
+        assert_eq!(
+            tree.display_list().damage,
+            [Rect::new(0.0, 0.0, 40.0, 40.0)]
+        );
+    }
+}
@@ -1,29 +1,116 @@
-use macroquad::shapes::draw_rectangle;
-
-use crate::capsule::{Capsule, obj::iter_all_objects};
-
-pub fn render_capsule(capsule: &Capsule) {
-    iter_all_objects(capsule, |o| {
-        o.map(|o| {
-            let binding = o.base();
-            let style = binding.style.read();
-            let computed = binding.computed_style.read();
-
-            if let Some(color) = style.background_color {
-                draw_rectangle(
-                    computed.x,
-                    computed.y,
-                    computed.width,
-                    computed.height,
-                    color.as_macroquad(),
-                );
-            }
-
-            drop(style);
-            drop(computed);
-            drop(binding);
-
-            o.render();
-        });
-    });
+use macroquad::math::Rect;
+
+use crate::{
+    capsule::obj::CapsuleObject,
+    layout::{
+        capsule::{border_style::COBorderStyle, color::COColor},
+        computed::ComputedStyling,
+    },
+    renderer::{
+        backend::Renderer,
+        shape::{RoundedRect, border_polygons, shadow_layers},
+    },
+};
+
+fn fill_rounded_rect(renderer: &mut dyn Renderer, shape: &RoundedRect, color: COColor) {
+    if shape.is_square() {
+        renderer.fill_rect(shape.rect, color);
+    } else {
+        renderer.fill_polygon(&shape.outline(), color);
+    }
+}
+
+/// Draws the shadows of an object, the first one ending up on top
+fn render_shadows(renderer: &mut dyn Renderer, computed: &ComputedStyling, shape: &RoundedRect) {
+    for shadow in computed.box_shadow.iter().rev() {
+        let mut shadow_shape = shape.outset(shadow.spread);
+        shadow_shape.rect = shadow_shape.rect.offset(shadow.offset);
+
+        let (layers, alpha) = shadow_layers(&shadow_shape, shadow.blur, shadow.color.a);
+
+        for layer in layers {
+            fill_rounded_rect(renderer, &layer, shadow.color.with_alpha(alpha));
+        }
+    }
+}
+
+fn render_border(
+    renderer: &mut dyn Renderer,
+    computed: &ComputedStyling,
+    shape: &RoundedRect,
+    style: COBorderStyle,
+    color: COColor,
+) {
+    let border = computed.border;
+
+    if style != COBorderStyle::Solid || !shape.is_square() {
+        for polygon in border_polygons(shape, border, style) {
+            renderer.fill_polygon(&polygon, color);
+        }
+        return;
+    }
+
+    let inner_height = computed.height - border.vertical();
+
+    renderer.fill_rect(
+        Rect::new(computed.x, computed.y, computed.width, border.top),
+        color,
+    );
+    renderer.fill_rect(
+        Rect::new(
+            computed.x,
+            computed.y + computed.height - border.bottom,
+            computed.width,
+            border.bottom,
+        ),
+        color,
+    );
+    renderer.fill_rect(
+        Rect::new(
+            computed.x,
+            computed.y + border.top,
+            border.left,
+            inner_height,
+        ),
+        color,
+    );
+    renderer.fill_rect(
+        Rect::new(
+            computed.x + computed.width - border.right,
+            computed.y + border.top,
+            border.right,
+            inner_height,
+        ),
+        color,
+    );
+}
+
+/// Draws the shadows, background and border of `o`, then its content
+pub fn render_object(o: &dyn CapsuleObject, renderer: &mut dyn Renderer) {
+    let binding = o.base();
+    let style = binding.style.read();
+    let computed = binding.computed_style.read();
+    let shape = RoundedRect::new(
+        Rect::new(computed.x, computed.y, computed.width, computed.height),
+        computed.border_radius,
+    );
+
+    renderer.set_transform(computed.transform);
+    renderer.set_clip(computed.clip);
+
+    render_shadows(renderer, &computed, &shape);
+
+    if let Some(color) = style.background_color {
+        fill_rounded_rect(renderer, &shape, color);
+    }
+
+    if let Some(color) = style.border_color {
+        render_border(renderer, &computed, &shape, style.border_style, color);
+    }
+
+    drop(style);
+    drop(computed);
+    drop(binding);
+
+    o.render(renderer);
 }
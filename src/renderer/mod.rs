@@ -1,3 +1,11 @@
+pub mod backend;
+pub mod bitmap;
 pub mod constants;
+pub mod display_list;
 pub mod full;
+pub mod shape;
+#[cfg(test)]
+pub mod snapshot;
+pub mod software;
 pub mod text;
+pub mod window;
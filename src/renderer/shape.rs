@@ -0,0 +1,294 @@
+//! Decorations broken down into convex polygons, so every renderer draws
+//! them from the same geometry with [`Renderer::fill_polygon`].
+//!
+//! [`Renderer::fill_polygon`]: crate::renderer::backend::Renderer::fill_polygon
+
+use std::f32::consts::FRAC_PI_2;
+
+use macroquad::math::{Rect, Vec2};
+
+use crate::layout::{
+    capsule::border_style::COBorderStyle,
+    computed::{ComputedCorners, ComputedEdges},
+};
+
+/// Segments each rounded corner is made of
+const CORNER_SEGMENTS: usize = 8;
+
+/// Most layers a blurred shadow is made of
+const MAX_SHADOW_LAYERS: usize = 12;
+
+/// A rect with rounded corners, each with a horizontal and vertical radius,
+/// clockwise from the top left
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RoundedRect {
+    pub rect: Rect,
+    pub radii: [Vec2; 4],
+}
+
+impl RoundedRect {
+    /// Like CSS, radii that don't fit along a side are all scaled down
+    /// until they do
+    #[must_use]
+    pub fn new(rect: Rect, corners: ComputedCorners) -> Self {
+        let radii = [
+            corners.top_left,
+            corners.top_right,
+            corners.bottom_right,
+            corners.bottom_left,
+        ];
+        let fits = |side: f32, a: f32, b: f32| if a + b > 0.0 { side / (a + b) } else { 1.0 };
+        let scale = [
+            fits(rect.w, radii[0], radii[1]),
+            fits(rect.h, radii[1], radii[2]),
+            fits(rect.w, radii[2], radii[3]),
+            fits(rect.h, radii[3], radii[0]),
+        ]
+        .into_iter()
+        .fold(1.0, f32::min)
+        .max(0.0);
+
+        Self {
+            rect,
+            radii: radii.map(|radius| Vec2::splat(radius * scale)),
+        }
+    }
+
+    /// Whether none of the corners are rounded
+    #[must_use]
+    pub fn is_square(&self) -> bool {
+        self.radii.iter().all(|radius| *radius == Vec2::ZERO)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rect.w <= 0.0 || self.rect.h <= 0.0
+    }
+
+    /// Grows every side by `amount`, or shrinks them when it's negative. The
+    /// radii grow and shrink with them.
+    #[must_use]
+    pub fn outset(&self, amount: f32) -> Self {
+        let rect = Rect::new(
+            self.rect.x - amount,
+            self.rect.y - amount,
+            (self.rect.w + amount * 2.0).max(0.0),
+            (self.rect.h + amount * 2.0).max(0.0),
+        );
+        let radii = self.radii.map(|radius| {
+            if radius == Vec2::ZERO {
+                radius
+            } else {
+                (radius + amount).max(Vec2::ZERO)
+            }
+        });
+
+        Self { rect, radii }
+    }
+
+    /// The shape inside of a border `widths` wide
+    #[must_use]
+    pub fn inset(&self, widths: ComputedEdges) -> Self {
+        let rect = Rect::new(
+            self.rect.x + widths.left,
+            self.rect.y + widths.top,
+            (self.rect.w - widths.horizontal()).max(0.0),
+            (self.rect.h - widths.vertical()).max(0.0),
+        );
+        let [top_left, top_right, bottom_right, bottom_left] = self.radii;
+        let radii = [
+            top_left - Vec2::new(widths.left, widths.top),
+            top_right - Vec2::new(widths.right, widths.top),
+            bottom_right - Vec2::new(widths.right, widths.bottom),
+            bottom_left - Vec2::new(widths.left, widths.bottom),
+        ]
+        .map(|radius| radius.max(Vec2::ZERO));
+
+        Self { rect, radii }
+    }
+
+    /// Points around the shape, clockwise from the start of the top left
+    /// corner. Every corner has the same number of points, even the square
+    /// ones, so the outline of a shape inset from another lines up with it.
+    #[must_use]
+    pub fn outline(&self) -> Vec<Vec2> {
+        let Rect { x, y, w, h } = self.rect;
+        let [top_left, top_right, bottom_right, bottom_left] = self.radii;
+        // where each arc starts, seen from the center of its corner
+        let corners = [
+            (Vec2::new(x, y) + top_left, top_left, Vec2::NEG_X),
+            (
+                Vec2::new(x + w - top_right.x, y + top_right.y),
+                top_right,
+                Vec2::NEG_Y,
+            ),
+            (
+                Vec2::new(x + w, y + h) - bottom_right,
+                bottom_right,
+                Vec2::X,
+            ),
+            (
+                Vec2::new(x + bottom_left.x, y + h - bottom_left.y),
+                bottom_left,
+                Vec2::Y,
+            ),
+        ];
+
+        corners
+            .into_iter()
+            .flat_map(|(center, radius, start)| {
+                (0..=CORNER_SEGMENTS).map(move |i| {
+                    // the ends are exact, so corners without a side between
+                    // them meet on the same point
+                    let direction = match i {
+                        0 => start,
+                        CORNER_SEGMENTS => start.perp(),
+                        _ => {
+                            #[allow(clippy::cast_precision_loss)]
+                            let angle = FRAC_PI_2 * i as f32 / CORNER_SEGMENTS as f32;
+                            Vec2::from_angle(angle).rotate(start)
+                        }
+                    };
+                    center + radius * direction
+                })
+            })
+            .collect()
+    }
+}
+
+/// The polygons of a border `widths` wide along the inside of `shape`.
+/// Dashes and dots are laid out along the straight part of each side, the
+/// corners are always drawn solid.
+#[must_use]
+pub fn border_polygons(
+    shape: &RoundedRect,
+    widths: ComputedEdges,
+    style: COBorderStyle,
+) -> Vec<Vec<Vec2>> {
+    if style == COBorderStyle::None {
+        return Vec::new();
+    }
+
+    let outer = shape.outline();
+    let inner = shape.inset(widths).outline();
+    let count = outer.len();
+    let mut polygons = Vec::new();
+
+    for i in 0..count {
+        let next = (i + 1) % count;
+        let quad = |from: f32, to: f32| {
+            vec![
+                outer[i].lerp(outer[next], from),
+                outer[i].lerp(outer[next], to),
+                inner[i].lerp(inner[next], to),
+                inner[i].lerp(inner[next], from),
+            ]
+        };
+
+        // every corner ends with a side going to the next one
+        let side = (i % (CORNER_SEGMENTS + 1) == CORNER_SEGMENTS).then(|| {
+            [widths.top, widths.right, widths.bottom, widths.left][i / (CORNER_SEGMENTS + 1)]
+        });
+        let (dash, gap) = match (style, side) {
+            (COBorderStyle::Dashed, Some(width)) => (width * 2.0, width),
+            (COBorderStyle::Dotted, Some(width)) => (width, width),
+            _ => {
+                polygons.push(quad(0.0, 1.0));
+                continue;
+            }
+        };
+
+        let length = outer[i].distance(outer[next]);
+        if dash <= 0.0 || length <= 0.0 {
+            continue;
+        }
+
+        let mut start = 0.0;
+        while start < length {
+            polygons.push(quad(start / length, ((start + dash) / length).min(1.0)));
+            start += dash + gap;
+        }
+    }
+
+    for polygon in &mut polygons {
+        polygon.dedup();
+        if polygon.len() > 1 && polygon.first() == polygon.last() {
+            polygon.pop();
+        }
+    }
+
+    polygons.retain(|polygon| polygon.len() >= 3);
+    polygons
+}
+
+/// Shapes that, each filled with the alpha returned next to them, add up to
+/// `shape` with its edge faded out over `blur`
+#[must_use]
+pub fn shadow_layers(shape: &RoundedRect, blur: f32, alpha: f32) -> (Vec<RoundedRect>, f32) {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let count = ((blur / 2.0).ceil() as usize).clamp(1, MAX_SHADOW_LAYERS);
+
+    // stacked, the layers are as opaque as `alpha` where they all overlap
+    #[allow(clippy::cast_precision_loss)]
+    let layer_alpha = 1.0 - (1.0 - alpha).powf(1.0 / count as f32);
+
+    let layers = (0..count)
+        .map(|i| {
+            #[allow(clippy::cast_precision_loss)]
+            let step = (i as f32 + 0.5) / count as f32;
+            shape.outset(blur / 2.0 - blur * step)
+        })
+        .filter(|layer| !layer.is_empty())
+        .collect();
+
+    (layers, layer_alpha)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radii_are_scaled_to_fit() {
+        let shape = RoundedRect::new(
+            Rect::new(0.0, 0.0, 80.0, 40.0),
+            ComputedCorners {
+                top_left: 40.0,
+                top_right: 40.0,
+                bottom_right: 0.0,
+                bottom_left: 0.0,
+            },
+        );
+        assert_eq!(shape.radii[0], Vec2::splat(40.0));
+
+        let shape = RoundedRect::new(
+            Rect::new(0.0, 0.0, 40.0, 40.0),
+            ComputedCorners {
+                top_left: 40.0,
+                top_right: 40.0,
+                bottom_right: 0.0,
+                bottom_left: 0.0,
+            },
+        );
+        assert_eq!(shape.radii[0], Vec2::splat(20.0));
+        assert_eq!(shape.radii[2], Vec2::ZERO);
+    }
+
+    #[test]
+    fn circle_corners_meet() {
+        let circle = RoundedRect::new(
+            Rect::new(0.0, 0.0, 80.0, 80.0),
+            ComputedCorners {
+                top_left: 40.0,
+                top_right: 40.0,
+                bottom_right: 40.0,
+                bottom_left: 40.0,
+            },
+        );
+        let outline = circle.outline();
+
+        assert_eq!(outline[CORNER_SEGMENTS], outline[CORNER_SEGMENTS + 1]);
+        assert_eq!(outline[CORNER_SEGMENTS], Vec2::new(40.0, 0.0));
+        assert_eq!(outline[0], Vec2::new(0.0, 40.0));
+    }
+}
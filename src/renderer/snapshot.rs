@@ -0,0 +1,241 @@
+//! Golden image tests. A capsule is rendered headlessly and compared with a
+//! reference image in `snapshots/`. Run the tests with `UPDATE_SNAPSHOTS=1`
+//! to write the references instead of comparing against them.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, ensure};
+use macroquad::math::Vec2;
+use parking_lot::RwLock;
+
+use crate::{
+    capsule::{Capsule, parser::parse_capsule_file},
+    event::update::{InputEvent, dispatch_input},
+    layout::{capsule::color::BLACK, computer::LayoutTree},
+    renderer::{bitmap::Bitmap, software::SoftwareRenderer},
+};
+
+/// Color of the pixels that differ in diff images
+const DIFF_COLOR: [u8; 4] = [255, 0, 0, 255];
+
+pub struct Snapshot {
+    /// path of the `.capsule` file, relative to the crate root
+    pub capsule: &'static str,
+    pub viewport: Vec2,
+    /// replayed in order once the scripts ran, the layout is updated and
+    /// repainted after every event like it would be after every frame
+    pub input: Vec<InputEvent>,
+    /// how far a channel of a pixel may be off before it counts as changed
+    pub tolerance: u8,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            capsule: "test.capsule",
+            viewport: Vec2::new(800.0, 600.0),
+            input: Vec::new(),
+            tolerance: 2,
+        }
+    }
+}
+
+fn crate_path(path: impl AsRef<Path>) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+impl Snapshot {
+    pub fn render(&self) -> anyhow::Result<Bitmap> {
+        let (mut capsule, diagnostics) = parse_capsule_file(crate_path(self.capsule))?;
+        ensure!(diagnostics.is_empty(), "{}: {diagnostics:?}", self.capsule);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let mut renderer =
+            SoftwareRenderer::new(self.viewport.x as u32, self.viewport.y as u32, BLACK);
+        capsule.viewport = self.viewport;
+        let mut layout = LayoutTree::new(&capsule, Arc::new(renderer.measurer()));
+
+        let capsule = Arc::new(RwLock::new(capsule));
+        Capsule::run_scripts(&capsule.clone());
+        layout.update();
+        layout.display_list_mut().repaint(&mut renderer, BLACK);
+
+        // only what the input damaged is painted again, like in the window
+        for event in &self.input {
+            dispatch_input(&capsule, *event);
+            layout.update();
+            layout.display_list_mut().repaint(&mut renderer, BLACK);
+        }
+
+        Ok(renderer.bitmap().clone())
+    }
+
+    /// Renders the capsule and compares it with `snapshots/{name}.png`. When
+    /// they differ, the render and an image with the changed pixels in red
+    /// are written to `target/snapshots`.
+    pub fn check(&self, name: &str) -> anyhow::Result<()> {
+        let actual = self.render()?;
+        let reference_path = crate_path(format!("snapshots/{name}.png"));
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            return actual.write_png(reference_path);
+        }
+
+        let reference = Bitmap::read_png(&reference_path)?;
+        ensure!(
+            (actual.width, actual.height) == (reference.width, reference.height),
+            "{name} is {}x{}, but its reference is {}x{}",
+            actual.width,
+            actual.height,
+            reference.width,
+            reference.height
+        );
+
+        let mut diff = reference.clone();
+        let mut changed = 0;
+
+        for (actual, diff) in actual
+            .pixels
+            .chunks_exact(4)
+            .zip(diff.pixels.chunks_exact_mut(4))
+        {
+            if actual
+                .iter()
+                .zip(diff.iter())
+                .any(|(a, r)| a.abs_diff(*r) > self.tolerance)
+            {
+                diff.copy_from_slice(&DIFF_COLOR);
+                changed += 1;
+            } else {
+                // keep the reference recognizable but out of the way
+                for channel in &mut diff[..3] {
+                    *channel /= 4;
+                }
+            }
+        }
+
+        if changed == 0 {
+            return Ok(());
+        }
+
+        let output = crate_path("target/snapshots");
+        std::fs::create_dir_all(&output)?;
+        actual.write_png(output.join(format!("{name}.png")))?;
+        diff.write_png(output.join(format!("{name}.diff.png")))?;
+
+        bail!(
+            "{changed} pixels of {name} differ from {}, see {}",
+            reference_path.display(),
+            output.display()
+        )
+    }
+}
+
+#[test]
+fn test_capsule() {
+    Snapshot::default().check("test_capsule").unwrap();
+}
+
+#[test]
+fn clicks_and_scrolling() {
+    Snapshot {
+        capsule: "snapshots/input.capsule",
+        viewport: Vec2::new(200.0, 150.0),
+        input: vec![
+            InputEvent::Click {
+                position: Vec2::new(20.0, 20.0),
+                button: 1,
+            },
+            InputEvent::Scroll {
+                position: Vec2::new(100.0, 100.0),
+                delta: Vec2::new(0.0, -0.5),
+            },
+        ],
+        ..Snapshot::default()
+    }
+    .check("input")
+    .unwrap();
+}
+
+#[test]
+fn changes_are_caught() {
+    let snapshot = Snapshot {
+        capsule: "snapshots/input.capsule",
+        viewport: Vec2::new(200.0, 150.0),
+        ..Snapshot::default()
+    };
+
+    // without the input, the click and scroll are missing
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_none() {
+        let error = snapshot.check("input").unwrap_err().to_string();
+        assert!(error.contains("pixels of input differ"), "{error}");
+    }
+}
+
+#[test]
+fn decorations() {
+    Snapshot {
+        capsule: "snapshots/decorations.capsule",
+        viewport: Vec2::new(400.0, 300.0),
+        ..Snapshot::default()
+    }
+    .check("decorations")
+    .unwrap();
+}
+
+#[test]
+fn images() {
+    Snapshot {
+        capsule: "snapshots/images.capsule",
+        viewport: Vec2::new(320.0, 240.0),
+        // swaps one image for a JPEG, and another one for its alt text
+        input: vec![
+            InputEvent::Click {
+                position: Vec2::new(260.0, 20.0),
+                button: 1,
+            },
+            InputEvent::Click {
+                position: Vec2::new(110.0, 180.0),
+                button: 1,
+            },
+        ],
+        ..Snapshot::default()
+    }
+    .check("images")
+    .unwrap();
+}
+
+#[test]
+fn fonts() {
+    Snapshot {
+        capsule: "snapshots/fonts.capsule",
+        viewport: Vec2::new(320.0, 320.0),
+        // switches a line back to the default font from Lua
+        input: vec![InputEvent::Click {
+            position: Vec2::new(40.0, 118.0),
+            button: 1,
+        }],
+        ..Snapshot::default()
+    }
+    .check("fonts")
+    .unwrap();
+}
+
+#[test]
+fn rich_text() {
+    Snapshot {
+        capsule: "snapshots/rich_text.capsule",
+        viewport: Vec2::new(320.0, 320.0),
+        // replaces a text's runs with plain text from Lua
+        input: vec![InputEvent::Click {
+            position: Vec2::new(20.0, 258.0),
+            button: 1,
+        }],
+        ..Snapshot::default()
+    }
+    .check("rich_text")
+    .unwrap();
+}
@@ -0,0 +1,376 @@
+use std::sync::Arc;
+
+use fontdue::{Font, FontSettings, Metrics};
+use macroquad::math::{Affine2, Rect, Vec2};
+
+use crate::{
+    layout::{
+        capsule::color::COColor,
+        font::{FontFace, FontSynthesis},
+        text::TextMeasurer,
+    },
+    renderer::{backend::Renderer, bitmap::Bitmap, constants::DEFAULT_FONT},
+};
+
+/// Measures text with the font [`SoftwareRenderer`] draws it with
+#[derive(Clone)]
+pub struct FontMeasurer {
+    font: Arc<Font>,
+}
+
+impl TextMeasurer for FontMeasurer {
+    fn line_width(&self, text: &str, font: Option<&Arc<FontFace>>, font_size: f32) -> f32 {
+        let font = font.map_or(&*self.font, |font| font.fontdue());
+
+        text.chars()
+            .map(|c| font.metrics(c, font_size).advance_width)
+            .sum()
+    }
+
+    fn ascent(&self, text: &str, font: Option<&Arc<FontFace>>, font_size: f32) -> f32 {
+        let font = font.map_or(&*self.font, |font| font.fontdue());
+
+        ascent(text.chars().map(|c| font.metrics(c, font_size)))
+    }
+}
+
+/// How far the tallest of the glyphs reaches above the baseline
+fn ascent(glyphs: impl IntoIterator<Item = Metrics>) -> f32 {
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+    glyphs
+        .into_iter()
+        .map(|metrics| (metrics.height as i32 + metrics.ymin) as f32)
+        .fold(0.0, f32::max)
+}
+
+/// Whether `point` is in `rect`, counting its top and left edges but not its
+/// bottom and right ones, so rects that touch never share a pixel
+fn covers(rect: Rect, point: Vec2) -> bool {
+    point.x >= rect.x && point.x < rect.right() && point.y >= rect.y && point.y < rect.bottom()
+}
+
+/// Whether `point` is in the convex polygon going clockwise through
+/// `points`. Like [`covers`], points on its top and left edges count and
+/// points on its bottom and right ones don't, so polygons sharing an edge
+/// never share a pixel.
+fn polygon_covers(points: &[Vec2], point: Vec2) -> bool {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .all(|(from, to)| {
+            let edge = *to - *from;
+            let side = edge.perp_dot(point - *from);
+            let top_left = edge.y < 0.0 || (edge.y == 0.0 && edge.x > 0.0);
+
+            side > 0.0 || (side == 0.0 && top_left)
+        })
+}
+
+/// Draws into an RGBA buffer on the CPU, without a window or GPU. Shapes
+/// cover the pixels whose centers they contain, with no anti-aliasing, so
+/// the same capsule always renders to the same pixels.
+pub struct SoftwareRenderer {
+    target: Bitmap,
+    transform: Affine2,
+    clip: Option<Rect>,
+    font: Arc<Font>,
+}
+
+impl SoftwareRenderer {
+    #[must_use]
+    pub fn new(width: u32, height: u32, background: COColor) -> Self {
+        let font = Font::from_bytes(DEFAULT_FONT, FontSettings::default())
+            .expect("the default font is valid");
+        Self {
+            target: Bitmap::filled(width, height, background.into()),
+            transform: Affine2::IDENTITY,
+            clip: None,
+            font: Arc::new(font),
+        }
+    }
+
+    /// A measurer that agrees with how this renderer draws text
+    #[must_use]
+    pub fn measurer(&self) -> FontMeasurer {
+        FontMeasurer {
+            font: self.font.clone(),
+        }
+    }
+
+    /// What was drawn so far
+    #[must_use]
+    pub const fn bitmap(&self) -> &Bitmap {
+        &self.target
+    }
+
+    /// Blends `color` over the pixel at `x`, `y`, `coverage` scales its alpha
+    fn blend(&mut self, x: u32, y: u32, color: COColor, coverage: f32) {
+        let alpha = color.a * coverage;
+
+        if alpha <= 0.0 {
+            return;
+        }
+
+        let pixel = self.target.pixel_mut(x, y);
+
+        for (channel, source) in pixel.iter_mut().zip([color.r, color.g, color.b, 1.0]) {
+            let destination = f32::from(*channel) / 255.0;
+            let blended = source.mul_add(alpha, destination * (1.0 - alpha));
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            {
+                *channel = (blended * 255.0).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+
+    /// Paints `color` over every pixel `rect` covers once transformed, with
+    /// the coverage `sample` returns for the point of `rect` the pixel's
+    /// center maps back to
+    fn paint<F>(&mut self, rect: Rect, color: COColor, sample: F)
+    where
+        F: Fn(Vec2) -> f32,
+    {
+        self.shade(rect, |point| color.with_alpha(color.a * sample(point)));
+    }
+
+    /// Like [`SoftwareRenderer::paint`], with the color of every pixel
+    /// coming from `shader`
+    fn shade<F>(&mut self, rect: Rect, shader: F)
+    where
+        F: Fn(Vec2) -> COColor,
+    {
+        let corners = [
+            rect.point(),
+            Vec2::new(rect.right(), rect.y),
+            Vec2::new(rect.x, rect.bottom()),
+            Vec2::new(rect.right(), rect.bottom()),
+        ]
+        .map(|corner| self.transform.transform_point2(corner));
+        let min = corners.into_iter().reduce(Vec2::min).unwrap();
+        let max = corners.into_iter().reduce(Vec2::max).unwrap();
+
+        #[allow(clippy::cast_precision_loss)]
+        let mut bounds = Rect::new(
+            0.0,
+            0.0,
+            self.target.width as f32,
+            self.target.height as f32,
+        );
+        for limit in [
+            Some(Rect::new(min.x, min.y, max.x - min.x, max.y - min.y)),
+            self.clip,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let Some(intersection) = bounds.intersect(limit) else {
+                return;
+            };
+            bounds = intersection;
+        }
+
+        let inverse = self.transform.inverse();
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        for y in bounds.y.floor() as u32..bounds.bottom().ceil() as u32 {
+            for x in bounds.x.floor() as u32..bounds.right().ceil() as u32 {
+                #[allow(clippy::cast_precision_loss)]
+                let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+
+                if !self.clip.is_none_or(|clip| covers(clip, center)) {
+                    continue;
+                }
+
+                let local = inverse.transform_point2(center);
+
+                if covers(rect, local) {
+                    self.blend(x, y, shader(local), 1.0);
+                }
+            }
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn set_transform(&mut self, transform: Affine2) {
+        self.transform = transform;
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip;
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: COColor) {
+        self.paint(rect, color, |_| 1.0);
+    }
+
+    fn fill_polygon(&mut self, points: &[Vec2], color: COColor) {
+        // repeated points would make edges without a direction
+        let mut points = points.to_vec();
+        points.dedup();
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+
+        if points.len() < 3 {
+            return;
+        }
+
+        let min = points.iter().copied().reduce(Vec2::min).unwrap();
+        let max = points.iter().copied().reduce(Vec2::max).unwrap();
+
+        self.paint(
+            Rect::new(min.x, min.y, max.x - min.x, max.y - min.y),
+            color,
+            |point| {
+                if polygon_covers(&points, point) {
+                    1.0
+                } else {
+                    0.0
+                }
+            },
+        );
+    }
+
+    fn draw_image(&mut self, image: &Arc<Bitmap>, source: Rect, destination: Rect) {
+        if image.width == 0 || image.height == 0 || destination.w <= 0.0 || destination.h <= 0.0 {
+            return;
+        }
+
+        let scale = source.size() / destination.size();
+
+        // nearest neighbor, like the rest of this renderer it doesn't smooth
+        self.shade(destination, |point| {
+            let texel = source.point() + (point - destination.point()) * scale;
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let [r, g, b, a] = image.pixel(
+                (texel.x.max(0.0) as u32).min(image.width - 1),
+                (texel.y.max(0.0) as u32).min(image.height - 1),
+            );
+
+            COColor::from_rgba(r, g, b, a)
+        });
+    }
+
+    fn draw_text(
+        &mut self,
+        text: &str,
+        position: Vec2,
+        font: Option<&Arc<FontFace>>,
+        synthesis: FontSynthesis,
+        font_size: f32,
+        color: COColor,
+    ) {
+        let font = font.map_or(&*self.font, |font| font.fontdue());
+        let glyphs: Vec<_> = text.chars().map(|c| font.rasterize(c, font_size)).collect();
+
+        // like macroquad, the top of the tallest glyph goes at `position`
+        let baseline = position.y + ascent(glyphs.iter().map(|(metrics, _)| *metrics));
+        let slant = if synthesis.italic {
+            FontSynthesis::ITALIC_SLANT
+        } else {
+            0.0
+        };
+
+        let mut offsets = vec![0.0];
+        if synthesis.bold {
+            offsets.push(synthesis.bold_offset(font_size));
+        }
+
+        for offset in offsets {
+            let mut x = position.x + offset;
+
+            for (metrics, bitmap) in &glyphs {
+                #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+                let rect = Rect::new(
+                    x + metrics.xmin as f32,
+                    baseline - (metrics.height as i32 + metrics.ymin) as f32,
+                    metrics.width as f32,
+                    metrics.height as f32,
+                );
+
+                if metrics.width > 0 && metrics.height > 0 {
+                    // italic glyphs lean right above the baseline and left
+                    // below it
+                    let lean = |y: f32| slant * (baseline - y);
+                    let left = lean(rect.y).min(lean(rect.bottom()));
+                    let right = lean(rect.y).max(lean(rect.bottom()));
+                    let bounds = Rect::new(rect.x + left, rect.y, rect.w + right - left, rect.h);
+
+                    self.paint(bounds, color, |point| {
+                        let x = point.x - lean(point.y);
+
+                        if x < rect.x || x >= rect.right() {
+                            return 0.0;
+                        }
+
+                        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                        let (column, row) = (
+                            ((x - rect.x) as usize).min(metrics.width - 1),
+                            ((point.y - rect.y) as usize).min(metrics.height - 1),
+                        );
+
+                        f32::from(bitmap[row * metrics.width + column]) / 255.0
+                    });
+                }
+
+                x += metrics.advance_width;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::capsule::color::{BLACK, RED, WHITE};
+
+    #[test]
+    fn rects_are_transformed_and_clipped() {
+        let mut renderer = SoftwareRenderer::new(20, 20, BLACK);
+
+        renderer.set_clip(Some(Rect::new(0.0, 0.0, 20.0, 10.0)));
+        renderer.set_transform(Affine2::from_translation(Vec2::new(5.0, 5.0)));
+        renderer.fill_rect(Rect::new(0.0, 0.0, 4.0, 10.0), WHITE);
+
+        assert_eq!(renderer.bitmap().pixel(5, 5), [255, 255, 255, 255]);
+        assert_eq!(renderer.bitmap().pixel(8, 9), [255, 255, 255, 255]);
+        assert_eq!(renderer.bitmap().pixel(9, 5), [0, 0, 0, 255]);
+        assert_eq!(renderer.bitmap().pixel(5, 10), [0, 0, 0, 255]);
+        assert_eq!(renderer.bitmap().pixel(4, 5), [0, 0, 0, 255]);
+
+        renderer.set_clip(None);
+        renderer.set_transform(Affine2::IDENTITY);
+        renderer.fill_rect(Rect::new(0.0, 0.0, 1.0, 1.0), RED.with_alpha(0.5));
+
+        assert_eq!(renderer.bitmap().pixel(0, 0), [115, 20, 28, 255]);
+    }
+
+    #[test]
+    fn text_is_drawn_where_it_is_measured() {
+        let mut renderer = SoftwareRenderer::new(100, 30, BLACK);
+        let width = renderer.measurer().line_width("Hi", None, 20.0);
+
+        renderer.draw_text(
+            "Hi",
+            Vec2::new(10.0, 5.0),
+            None,
+            FontSynthesis::default(),
+            20.0,
+            WHITE,
+        );
+
+        let bitmap = renderer.bitmap();
+        let lit: Vec<_> = (0..bitmap.width)
+            .filter(|x| (0..bitmap.height).any(|y| bitmap.pixel(*x, y)[0] > 0))
+            .collect();
+
+        assert!(width > 0.0);
+        assert!(lit.first().is_some_and(|x| *x >= 10));
+        #[allow(clippy::cast_precision_loss)]
+        let right = *lit.last().unwrap() as f32;
+        assert!(right < 10.0 + width);
+    }
+}
@@ -1,11 +1,123 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+
 use macroquad::{
     color::Color,
-    text::{draw_text, measure_text},
+    math::{Mat4, Vec4},
+    text::{Font, TextParams, draw_text_ex, load_ttf_font_from_bytes, measure_text},
+    window::get_internal_gl,
+};
+
+use crate::layout::{
+    font::{FontFace, FontSynthesis},
+    text::TextMeasurer,
 };
 
-pub fn draw_text_top_left(text: &str, x: f32, y: f32, font_size: f32, color: Color) {
+thread_local! {
+    /// macroquad's copies of the fonts used so far, by address, dropped with
+    /// the fonts. macroquad only runs on one thread, so they're kept on it.
+    static FONTS: RefCell<HashMap<usize, (Weak<FontFace>, Font)>> = RefCell::default();
+}
+
+/// macroquad's copy of `face`, loaded the first time it's used. `None` for
+/// the default font, and when macroquad can't load it.
+fn macroquad_font(face: Option<&Arc<FontFace>>) -> Option<Font> {
+    let face = face?;
+
+    FONTS.with_borrow_mut(|fonts| {
+        fonts.retain(|_, (face, _)| face.strong_count() > 0);
+
+        let key = Arc::as_ptr(face) as usize;
+        if let Some((_, font)) = fonts.get(&key) {
+            return Some(font.clone());
+        }
+
+        let font = load_ttf_font_from_bytes(face.bytes())
+            .inspect_err(|e| log::warn!("macroquad can't load {face:?}: {e}"))
+            .ok()?;
+        fonts.insert(key, (Arc::downgrade(face), font.clone()));
+
+        Some(font)
+    })
+}
+
+/// Measures text the way [`draw_text_top_left`] draws it, needs a window
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MacroquadMeasurer;
+
+impl TextMeasurer for MacroquadMeasurer {
+    fn line_width(&self, text: &str, font: Option<&Arc<FontFace>>, font_size: f32) -> f32 {
+        let font = macroquad_font(font);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        measure_text(text, font.as_ref(), font_size as u16, 1.0).width
+    }
+
+    fn ascent(&self, text: &str, font: Option<&Arc<FontFace>>, font_size: f32) -> f32 {
+        let font = macroquad_font(font);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        measure_text(text, font.as_ref(), font_size as u16, 1.0).offset_y
+    }
+}
+
+pub fn draw_text_top_left(
+    text: &str,
+    x: f32,
+    y: f32,
+    font: Option<&Arc<FontFace>>,
+    synthesis: FontSynthesis,
+    font_size: f32,
+    color: Color,
+) {
+    let font = macroquad_font(font);
+
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::cast_sign_loss)]
-    let dims = measure_text(text, None, font_size as u16, 1.0);
-    draw_text(text, x, y + dims.offset_y, font_size, color);
+    let dims = measure_text(text, font.as_ref(), font_size as u16, 1.0);
+    let baseline = y + dims.offset_y;
+
+    // macroquad can't slant text, so the model matrix shears it around the
+    // baseline instead
+    if synthesis.italic {
+        let slant = FontSynthesis::ITALIC_SLANT;
+
+        // SAFETY: only used to set the model matrix, in between draw calls
+        let gl = unsafe { get_internal_gl() };
+        gl.quad_gl.push_model_matrix(Mat4::from_cols(
+            Vec4::X,
+            Vec4::new(-slant, 1.0, 0.0, 0.0),
+            Vec4::Z,
+            Vec4::new(slant * baseline, 0.0, 0.0, 1.0),
+        ));
+    }
+
+    let mut offsets = vec![0.0];
+    if synthesis.bold {
+        offsets.push(synthesis.bold_offset(font_size));
+    }
+
+    for offset in offsets {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        draw_text_ex(
+            text,
+            x + offset,
+            baseline,
+            TextParams {
+                font: font.as_ref(),
+                font_size: font_size as u16,
+                color,
+                ..Default::default()
+            },
+        );
+    }
+
+    if synthesis.italic {
+        // SAFETY: pops the matrix pushed above, in between draw calls
+        let gl = unsafe { get_internal_gl() };
+        gl.quad_gl.pop_model_matrix();
+    }
 }
@@ -0,0 +1,214 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+
+use macroquad::{
+    camera::{Camera2D, set_camera, set_default_camera},
+    color::WHITE,
+    math::{Affine2, Mat4, Rect, Vec2, Vec4},
+    shapes::{draw_rectangle, draw_triangle},
+    texture::{
+        DrawTextureParams, FilterMode, RenderTarget, Texture2D, draw_texture_ex, render_target,
+    },
+    window::{get_internal_gl, screen_dpi_scale, screen_height, screen_width},
+};
+
+use crate::{
+    layout::{
+        capsule::color::COColor,
+        font::{FontFace, FontSynthesis},
+    },
+    renderer::{
+        backend::Renderer, bitmap::Bitmap, display_list::DisplayList, text::draw_text_top_left,
+    },
+};
+
+/// Draws to the macroquad window, needs a window and GL
+#[derive(Debug, Default)]
+pub struct WindowRenderer {
+    /// whether a model matrix was pushed that has to be popped again
+    transformed: bool,
+    /// images uploaded so far, by address, dropped with their images
+    textures: HashMap<usize, (Weak<Bitmap>, Texture2D)>,
+}
+
+impl WindowRenderer {
+    /// The texture of `image`, uploaded the first time it's drawn. `None`
+    /// when it's too large to be one.
+    fn texture(&mut self, image: &Arc<Bitmap>) -> Option<Texture2D> {
+        self.textures
+            .retain(|_, (image, _)| image.strong_count() > 0);
+
+        if let Some((_, texture)) = self.textures.get(&(Arc::as_ptr(image) as usize)) {
+            return Some(texture.clone());
+        }
+
+        let texture = Texture2D::from_rgba8(
+            u16::try_from(image.width).ok()?,
+            u16::try_from(image.height).ok()?,
+            &image.pixels,
+        );
+        self.textures.insert(
+            Arc::as_ptr(image) as usize,
+            (Arc::downgrade(image), texture.clone()),
+        );
+
+        Some(texture)
+    }
+}
+
+impl Renderer for WindowRenderer {
+    fn set_transform(&mut self, transform: Affine2) {
+        // SAFETY: only used to set the model matrix, in between draw calls
+        let gl = unsafe { get_internal_gl() };
+
+        if self.transformed {
+            gl.quad_gl.pop_model_matrix();
+        }
+
+        self.transformed = transform != Affine2::IDENTITY;
+
+        if self.transformed {
+            let Affine2 {
+                matrix2,
+                translation,
+            } = transform;
+
+            gl.quad_gl.push_model_matrix(Mat4::from_cols(
+                matrix2.x_axis.extend(0.0).extend(0.0),
+                matrix2.y_axis.extend(0.0).extend(0.0),
+                Vec4::Z,
+                translation.extend(0.0).extend(1.0),
+            ));
+        }
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        let scale = screen_dpi_scale();
+
+        // SAFETY: only used to set the scissor rect, in between draw calls
+        let gl = unsafe { get_internal_gl() };
+
+        #[allow(clippy::cast_possible_truncation)]
+        gl.quad_gl.scissor(clip.map(|clip| {
+            (
+                (clip.x * scale).floor() as i32,
+                (clip.y * scale).floor() as i32,
+                (clip.w * scale).ceil() as i32,
+                (clip.h * scale).ceil() as i32,
+            )
+        }));
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: COColor) {
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, color.as_macroquad());
+    }
+
+    fn fill_polygon(&mut self, points: &[Vec2], color: COColor) {
+        let Some((first, rest)) = points.split_first() else {
+            return;
+        };
+
+        for pair in rest.windows(2) {
+            draw_triangle(*first, pair[0], pair[1], color.as_macroquad());
+        }
+    }
+
+    fn draw_image(&mut self, image: &Arc<Bitmap>, source: Rect, destination: Rect) {
+        let Some(texture) = self.texture(image) else {
+            return;
+        };
+
+        draw_texture_ex(
+            &texture,
+            destination.x,
+            destination.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(destination.size()),
+                source: Some(source),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn draw_text(
+        &mut self,
+        text: &str,
+        position: Vec2,
+        font: Option<&Arc<FontFace>>,
+        synthesis: FontSynthesis,
+        font_size: f32,
+        color: COColor,
+    ) {
+        draw_text_top_left(
+            text,
+            position.x,
+            position.y,
+            font,
+            synthesis,
+            font_size,
+            color.as_macroquad(),
+        );
+    }
+}
+
+/// Keeps what was painted in a texture the size of the window, so a frame
+/// only has to paint what was damaged since the last one
+#[derive(Default)]
+pub struct WindowCanvas {
+    /// with the window size and DPI scale it was made for
+    target: Option<(RenderTarget, Vec2, f32)>,
+    /// kept around for the textures it uploaded
+    renderer: WindowRenderer,
+}
+
+impl WindowCanvas {
+    /// Repaints the damaged parts of `display_list` and draws the result to
+    /// the window
+    pub fn paint(&mut self, display_list: &mut DisplayList, background: COColor) {
+        let size = Vec2::new(screen_width(), screen_height());
+        let scale = screen_dpi_scale();
+
+        if self
+            .target
+            .as_ref()
+            .is_none_or(|(_, old_size, old_scale)| *old_size != size || *old_scale != scale)
+        {
+            // scissor rects are in pixels, so the texture has as many
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let target = render_target(
+                (size.x * scale).ceil() as u32,
+                (size.y * scale).ceil() as u32,
+            );
+            target.texture.set_filter(FilterMode::Nearest);
+            self.target = Some((target, size, scale));
+            display_list.damage(Rect::new(0.0, 0.0, size.x, size.y));
+        }
+
+        let (target, ..) = self.target.as_ref().unwrap();
+
+        if display_list.is_damaged() {
+            let mut camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, size.x, size.y));
+            camera.render_target = Some(target.clone());
+
+            set_camera(&camera);
+            display_list.repaint(&mut self.renderer, background);
+            set_default_camera();
+        }
+
+        draw_texture_ex(
+            &target.texture,
+            0.0,
+            0.0,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(size),
+                // render targets end up upside down
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+    }
+}